@@ -0,0 +1,155 @@
+use crate::adif::{AdifField, AdifFile, AdifRecord};
+use pest::Parser;
+use pest::error::{Error, ErrorVariant};
+use pest::Position;
+
+#[derive(Parser)]
+#[grammar = r"adi.pest"]
+pub struct AdiParser;
+
+// Reads ADI file contents honouring the data length of every data specifier:
+// exactly LEN characters following the tag are taken as data, and anything
+// after them up to the next '<' is ignored (ADIF 3.1, section II.B.5).
+pub fn read_adi(input: &str) -> Result<AdifFile, Error<Rule>> {
+    let mut adif = AdifFile::default();
+    let mut record = AdifRecord::default();
+    let mut pos: usize = 0;
+    let mut text_start: usize = 0; // start of text following the last tag or its data
+
+    // A header is present if the first character is not '<'; its free text, also
+    // between data specifiers, is kept as preamble
+    let mut in_header = !input.starts_with('<');
+
+    while let Some(offset) = input[pos..].find('<') {
+        let tag_start = pos + offset;
+        let mut tag = match AdiParser::parse(Rule::tag, &input[tag_start..]) {
+            Ok(pairs) => pairs,
+            Err(_) => {
+                // Not an ADIF tag, treat '<' as ordinary text
+                pos = tag_start + 1;
+                continue;
+            }
+        };
+        if in_header {
+            adif.header.preamble.push_str(&input[text_start..tag_start]);
+        }
+        let pair = tag.next().unwrap();
+        pos = tag_start + pair.as_str().len();
+
+        match pair.as_rule() {
+            Rule::end_of_header => {
+                adif.header.fields = std::mem::take(&mut record.fields);
+                in_header = false;
+            }
+            Rule::end_of_record => {
+                adif.records.push(std::mem::take(&mut record));
+            }
+            Rule::field => {
+                let mut name = "";
+                let mut length: usize = 0;
+                let mut type_indicator = None;
+                for inner_pair in pair.into_inner() {
+                    match inner_pair.as_rule() {
+                        Rule::field_name => name = inner_pair.as_str(),
+                        Rule::data_length => {
+                            length = inner_pair.as_str().parse().map_err(|_| {
+                                custom_error(input, tag_start, "data length is out of range")
+                            })?;
+                        }
+                        Rule::type_indicator => {
+                            type_indicator = inner_pair.as_str().chars().next();
+                        }
+                        _ => continue,
+                    }
+                }
+
+                // Data length is a number of characters, not bytes
+                let data_end = match input[pos..].char_indices().nth(length) {
+                    Some((i, _)) => pos + i,
+                    None if input[pos..].chars().count() == length => input.len(),
+                    None => {
+                        return Err(custom_error(
+                            input,
+                            tag_start,
+                            &format!("data of field {} is shorter than {} characters", name, length),
+                        ));
+                    }
+                };
+                record.fields.insert(AdifField {
+                    name: name.to_string(),
+                    length,
+                    type_indicator,
+                    data: input[pos..data_end].to_string(),
+                });
+                pos = data_end;
+            }
+            _ => unreachable!(),
+        }
+        text_start = pos;
+    }
+
+    if !record.fields.is_empty() {
+        return Err(custom_error(input, input.len(), "last record is not terminated by <EOR>"));
+    }
+    if adif.records.is_empty() {
+        return Err(custom_error(input, input.len(), "no records found"));
+    }
+    Ok(adif)
+}
+
+fn custom_error(input: &str, pos: usize, message: &str) -> Error<Rule> {
+    Error::new_from_pos(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        Position::new(input, pos).unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_is_taken_by_declared_length() {
+        let input = "header text\n<EOH>\n\
+            <CALL:5>LY2AA <COMMENT:20>5 < 9\nline <EOR> two <QSO_DATE:8>20260106 <EOR>\n";
+        let adif = read_adi(input).unwrap();
+        assert_eq!(adif.header.preamble, "header text\n");
+        assert_eq!(adif.records.len(), 1);
+        let record = &adif.records[0];
+        assert_eq!(record.value("CALL"), Some("LY2AA"));
+        assert_eq!(record.fields.get("COMMENT").unwrap().data, "5 < 9\nline <EOR> two");
+        assert_eq!(record.value("QSO_DATE"), Some("20260106"));
+    }
+
+    #[test]
+    fn header_text_is_kept_up_to_first_tag() {
+        let input = "Log of <LY2XX> club\nsecond line\n<ADIF_VER:5>3.1.4 exported by\nlogger\n<EOH>\n\
+            <CALL:5>LY2AA <EOR> not header text\n";
+        let adif = read_adi(input).unwrap();
+        assert_eq!(
+            adif.header.preamble,
+            "Log of <LY2XX> club\nsecond line\n exported by\nlogger\n"
+        );
+        assert_eq!(adif.header.value("ADIF_VER"), Some("3.1.4"));
+        assert_eq!(adif.records[0].value("CALL"), Some("LY2AA"));
+    }
+
+    #[test]
+    fn data_length_counts_characters() {
+        let adif = read_adi("<NAME:6>Žilvė <CALL:5>LY2AA<EOR>").unwrap();
+        assert_eq!(adif.records[0].value("NAME"), Some("Žilvė"));
+        assert_eq!(adif.records[0].value("CALL"), Some("LY2AA"));
+    }
+
+    #[test]
+    fn short_data_is_an_error() {
+        assert!(read_adi("<CALL:9>LY2AA<EOR>").is_err());
+    }
+
+    #[test]
+    fn unterminated_record_is_an_error() {
+        assert!(read_adi("<CALL:5>LY2AA <EOR> <CALL:5>LY2AB").is_err());
+    }
+}
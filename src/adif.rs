@@ -0,0 +1,145 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+// ADIF data model shared by the ADI and ADX readers, written out in ADI format
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct AdifField {
+    pub name: String,                 // field name as written in the file
+    pub length: usize,                // data length declared by the file
+    pub type_indicator: Option<char>, // optional data type indicator
+    pub data: String,                 // raw data, exactly 'length' characters
+}
+
+impl AdifField {
+    pub fn new(name: &str, data: &str, type_indicator: Option<char>) -> Self {
+        AdifField {
+            name: name.to_string(),
+            length: data.chars().count(),
+            type_indicator,
+            data: data.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AdifField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}:{}", self.name, self.length)?;
+        if let Some(type_indicator) = self.type_indicator {
+            write!(f, ":{}", type_indicator)?;
+        }
+        write!(f, ">{}", self.data)
+    }
+}
+
+// Ordered collection of fields with case-insensitive lookup by field name
+#[derive(Debug, Clone, Default)]
+pub struct AdifFields {
+    fields: Vec<AdifField>,
+}
+
+impl AdifFields {
+    // Adds a field keeping file order; a repeated field name replaces the earlier value
+    pub fn insert(&mut self, field: AdifField) {
+        match self.position(&field.name) {
+            Some(i) => self.fields[i] = field,
+            None => self.fields.push(field),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AdifField> {
+        self.position(name).map(|i| &self.fields[i])
+    }
+
+    // Trimmed field data; absent and empty fields are both None
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .map(|field| field.data.trim())
+            .filter(|data| !data.is_empty())
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, AdifField> {
+        self.fields.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| field.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AdifHeader {
+    pub preamble: String, // free text at the start of the header
+    pub fields: AdifFields,
+}
+
+impl fmt::Display for AdifHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.preamble.trim_end())?;
+        for field in self.fields.iter() {
+            writeln!(f, "{}", field)?;
+        }
+        write!(f, "<EOH>")
+    }
+}
+
+impl AdifHeader {
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.fields.value(name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AdifRecord {
+    pub fields: AdifFields,
+}
+
+impl fmt::Display for AdifRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in self.fields.iter() {
+            write!(f, "{} ", field)?;
+        }
+        write!(f, "<EOR>")
+    }
+}
+
+impl AdifRecord {
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.fields.value(name)
+    }
+
+    // QSO start time from QSO_DATE and TIME_ON (HHMM or HHMMSS), None if either is missing
+    // or malformed
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        let date = NaiveDate::parse_from_str(self.value("QSO_DATE")?, "%Y%m%d").ok()?;
+        let time_on = self.value("TIME_ON")?;
+        let time = match time_on.len() {
+            6 => NaiveTime::parse_from_str(time_on, "%H%M%S").ok()?,
+            _ => NaiveTime::parse_from_str(time_on.get(..4)?, "%H%M").ok()?,
+        };
+        Some(date.and_time(time))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AdifFile {
+    pub header: AdifHeader,
+    pub records: Vec<AdifRecord>,
+}
+
+impl fmt::Display for AdifFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.header)?;
+        for record in self.records.iter() {
+            writeln!(f, "{}", record)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::adif::{AdifField, AdifFields, AdifFile, AdifRecord};
+use crate::error::Error;
+use roxmltree::{Document, Node};
+
+// Reads ADX (XML ADIF) file contents into the same model as the ADI reader.
+// Application-defined fields <APP PROGRAMID="P" FIELDNAME="F"> are named APP_P_F
+// and user-defined fields in records take the name from their FIELDNAME attribute,
+// the same names these fields have in ADI files.
+pub fn read_adx(input: &str) -> Result<AdifFile, Error> {
+    let xml = Document::parse(input).map_err(|err| Error::Parse(err.to_string()))?;
+    let root = xml.root_element();
+    if !root.has_tag_name("ADX") {
+        return Err(Error::Parse(format!(
+            "root element is <{}>, expected <ADX>",
+            root.tag_name().name()
+        )));
+    }
+
+    let mut adif = AdifFile::default();
+
+    for section in root.children().filter(Node::is_element) {
+        match section.tag_name().name() {
+            "HEADER" => {
+                adif.header.fields = read_fields(section, true)?;
+            }
+            "RECORDS" => {
+                for record in section.children().filter(Node::is_element) {
+                    if !record.has_tag_name("RECORD") {
+                        continue;
+                    }
+                    adif.records.push(AdifRecord {
+                        fields: read_fields(record, false)?,
+                    });
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    if adif.records.is_empty() {
+        return Err(Error::Parse("no records found".to_string()));
+    }
+    Ok(adif)
+}
+
+fn read_fields(parent: Node, is_header: bool) -> Result<AdifFields, Error> {
+    let mut fields = AdifFields::default();
+
+    for element in parent.children().filter(Node::is_element) {
+        let data = element.text().unwrap_or("");
+        let type_indicator = element.attribute("TYPE").and_then(|t| t.chars().next());
+        let name = match element.tag_name().name() {
+            "APP" => {
+                let program_id = element.attribute("PROGRAMID").unwrap_or("");
+                let field_name = element.attribute("FIELDNAME").unwrap_or("");
+                format!("APP_{}_{}", program_id, field_name)
+            }
+            "USERDEF" if is_header => {
+                // User-defined field definition: <USERDEF FIELDID="n">name</USERDEF>
+                let field_id = element.attribute("FIELDID").unwrap_or("");
+                format!("USERDEF{}", field_id)
+            }
+            "USERDEF" => match element.attribute("FIELDNAME") {
+                Some(field_name) => field_name.to_string(),
+                None => {
+                    let pos = parent.document().text_pos_at(element.range().start);
+                    return Err(Error::Parse(format!("USERDEF without FIELDNAME at {}", pos)));
+                }
+            },
+            tag_name => tag_name.to_string(),
+        };
+        fields.insert(AdifField::new(&name, data, type_indicator));
+    }
+    Ok(fields)
+}
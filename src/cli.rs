@@ -1,16 +1,22 @@
 pub use clap::Parser as clapParser;
+use clap::Subcommand;
 pub use std::path::PathBuf;
+use chrono::NaiveDateTime;
 
 
 #[derive(clapParser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CliArgs {
-    // Input file (ADI)
-    #[arg(index = 1, required = true, help = "ADI file" /*, short, long, value_name = "ADI file"*/)]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Input file (ADI, ADX or EDI)
+    #[arg(index = 1, required = true, help = "ADI or ADX file (EDI file to convert back to ADI)" /*, short, long, value_name = "ADI file"*/)]
     pub infile: Option<PathBuf>,
     
-    // Output file (EDI)
-    #[arg(index = 2, help = "EDI file (consider -f if not specified)" /*, short, long, value_name = "EDI file"*/)]
+    // Output file (EDI, or ADI for EDI input)
+    #[arg(index = 2, help = "EDI file, ADI file for EDI input (consider -f if not specified)" /*, short, long, value_name = "EDI file"*/)]
     pub outfile: Option<PathBuf>,
 
     // Default output to file
@@ -20,6 +26,82 @@ pub struct CliArgs {
     // No remarks in EDI file
     #[arg(short = 's', long = "skip-remarks")]
     pub skip_remarks: bool,
+
+    // Station profile (TOML) with REG1TEST header values
+    #[arg(short = 'c', long = "config", value_name = "FILE", help = "station profile (TOML) with REG1TEST header values")]
+    pub config: Option<PathBuf>,
+
+    // Same call in another mode is not a duplicate
+    #[arg(long = "dupe-by-mode", help = "count same call in another mode as a new QSO")]
+    pub dupe_by_mode: bool,
+
+    // Minutes after which the same call may be worked again
+    #[arg(long = "dupe-window", value_name = "MINUTES", default_value_t = 0, help = "allow same call again after MINUTES (0 - never)")]
+    pub dupe_window: u32,
+
+    // Contest start, QSOs before it are skipped
+    #[arg(long = "start", value_name = "YYYYMMDDHHMM", value_parser = parse_timestamp, help = "contest start (UTC), earlier QSOs are skipped")]
+    pub start: Option<NaiveDateTime>,
+
+    // Contest end, QSOs from it on are skipped
+    #[arg(long = "end", value_name = "YYYYMMDDHHMM", value_parser = parse_timestamp, help = "contest end (UTC), QSOs from this time on are skipped")]
+    pub end: Option<NaiveDateTime>,
+
+    // ADIF CONTEST_ID of contest QSOs
+    #[arg(long = "contest-id", value_name = "ID", help = "only QSOs with this ADIF CONTEST_ID")]
+    pub contest_id: Option<String>,
+
+    // Sent QSO numbers replaced by a new series
+    #[arg(long = "renumber", help = "renumber sent QSO numbers from 001 in chronological order")]
+    pub renumber: bool,
+
+    // Contest sessions of the built-in calendar converted separately
+    #[arg(long = "detect-contests", conflicts_with_all = ["start", "end"], help = "convert every contest session of the built-in calendar found in the log")]
+    pub detect_contests: bool,
+
+    // Pause in minutes that starts a new session
+    #[arg(long = "split-gap", value_name = "MINUTES", conflicts_with_all = ["detect_contests", "session"], help = "split QSOs into sessions separated by a pause of more than MINUTES")]
+    pub split_gap: Option<u32>,
+
+    // Explicit sessions
+    #[arg(long = "session", value_name = "START/END", value_parser = parse_window, conflicts_with_all = ["start", "end", "detect_contests"], help = "session (UTC) converted separately, YYYYMMDDHHMM/YYYYMMDDHHMM, may be repeated")]
+    pub session: Vec<(NaiveDateTime, NaiveDateTime)>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check EDI file against REG1TEST rules
+    Validate {
+        // EDI file to check
+        #[arg(help = "EDI file")]
+        file: PathBuf,
+    },
+    /// List contest sessions of the built-in calendar found in ADI or ADX file
+    Contests {
+        // ADI or ADX file to check
+        #[arg(help = "ADI or ADX file")]
+        file: PathBuf,
+    },
+}
+
+// UTC timestamp as YYYYMMDDHHMM or YYYY-MM-DD HH:MM
+fn parse_timestamp(value: &str) -> Result<NaiveDateTime, String> {
+    ["%Y%m%d%H%M", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .ok_or(format!("'{}' should be YYYYMMDDHHMM", value))
+}
+
+// Session as START/END timestamps
+fn parse_window(value: &str) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let (start, end) = value
+        .split_once('/')
+        .ok_or(format!("'{}' should be START/END", value))?;
+    let (start, end) = (parse_timestamp(start)?, parse_timestamp(end)?);
+    if start >= end {
+        return Err(format!("session '{}' ends before it starts", value));
+    }
+    Ok((start, end))
 }
 
 /*
@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::reg1test::Reg1testHeader;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Station profile with REG1TEST header values, keys are the REG1TEST field names:
+//
+//   TName = "IARU Region 1 VHF Contest"
+//   PSect = "SINGLE"
+//   RName = "John Doe"
+//   SPowe = "100"
+//
+//   [Modes]
+//   FT8 = 0
+//   "MFSK/FT4" = "exclude"
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StationProfile {
+    #[serde(rename = "TName")]
+    pub tname: Option<String>, // contest name
+    #[serde(rename = "PExch")]
+    pub pexch: Option<String>, // sent exchange
+    #[serde(rename = "PAdr1")]
+    pub padr1: Option<String>, // address of QTH, line 1
+    #[serde(rename = "PAdr2")]
+    pub padr2: Option<String>, // address of QTH, line 2
+    #[serde(rename = "PSect")]
+    pub psect: Option<String>, // section
+    #[serde(rename = "PClub")]
+    pub pclub: Option<String>, // club
+    #[serde(rename = "RName")]
+    pub rname: Option<String>, // name of responsible operator
+    #[serde(rename = "RCall")]
+    pub rcall: Option<String>, // callsign of responsible operator
+    #[serde(rename = "RAdr1")]
+    pub radr1: Option<String>, // address of responsible operator, line 1
+    #[serde(rename = "RAdr2")]
+    pub radr2: Option<String>, // address of responsible operator, line 2
+    #[serde(rename = "RPoCo")]
+    pub rpoco: Option<String>, // postal code
+    #[serde(rename = "RCity")]
+    pub rcity: Option<String>, // city
+    #[serde(rename = "RCoun")]
+    pub rcoun: Option<String>, // country
+    #[serde(rename = "RPhon")]
+    pub rphon: Option<String>, // phone number
+    #[serde(rename = "RHBBS")]
+    pub rhbbs: Option<String>, // home BBS or e-mail
+    #[serde(rename = "MOpe1")]
+    pub mope1: Option<String>, // operators, line 1
+    #[serde(rename = "MOpe2")]
+    pub mope2: Option<String>, // operators, line 2
+    #[serde(rename = "STXEq")]
+    pub stxeq: Option<String>, // TX equipment
+    #[serde(rename = "SPowe")]
+    pub spowe: Option<String>, // TX power in watts
+    #[serde(rename = "SRXEq")]
+    pub srxeq: Option<String>, // RX equipment
+    #[serde(rename = "SAnte")]
+    pub sante: Option<String>, // antenna
+    #[serde(rename = "SAntH")]
+    pub santh: Option<String>, // antenna height above ground;above sea level
+    #[serde(rename = "Modes", default)]
+    pub modes: BTreeMap<String, ModeMapping>, // ADIF MODE, SUBMODE or MODE/SUBMODE to mode code
+}
+
+// REG1TEST mode code of an ADIF mode, or QSOs in the mode are left out
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "ModeValue")]
+pub enum ModeMapping {
+    Code(char),
+    Exclude,
+}
+
+// Mode code as written in the profile: 0 to 9 as number or string, or "exclude"
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ModeValue {
+    Number(i64),
+    Text(String),
+}
+
+impl TryFrom<ModeValue> for ModeMapping {
+    type Error = String;
+
+    fn try_from(value: ModeValue) -> Result<Self, Self::Error> {
+        let text = match value {
+            ModeValue::Number(number) => number.to_string(),
+            ModeValue::Text(text) => text.trim().to_string(),
+        };
+        match text.as_str() {
+            code if code.len() == 1 && code.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(ModeMapping::Code(code.chars().next().unwrap()))
+            }
+            code if code.eq_ignore_ascii_case("exclude") => Ok(ModeMapping::Exclude),
+            code => Err(format!("mode code '{}' should be 0 to 9 or \"exclude\"", code)),
+        }
+    }
+}
+
+impl StationProfile {
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| Error::Io("cannot read config file".to_string(), err))?;
+        toml::from_str(&text)
+            .map_err(|err| Error::Parse(format!("cannot parse config file: {}", err)))
+    }
+
+    // Mapping of ADIF MODE and SUBMODE, keys are case-insensitive and looked up as
+    // MODE/SUBMODE, SUBMODE and MODE in this order
+    pub fn mode_mapping(&self, mode: &str, submode: &str) -> Option<ModeMapping> {
+        let (mode, submode) = (mode.trim(), submode.trim());
+        let keys = [format!("{}/{}", mode, submode), submode.to_string(), mode.to_string()];
+        keys.iter()
+            .filter(|key| !key.is_empty() && !key.starts_with('/') && !key.ends_with('/'))
+            .find_map(|key| {
+                self.modes
+                    .iter()
+                    .find(|(name, _)| name.trim().eq_ignore_ascii_case(key))
+                    .map(|(_, mapping)| *mapping)
+            })
+    }
+
+    // Copies values present in the profile into the header
+    pub fn apply_to(&self, header: &mut Reg1testHeader) {
+        let fields = [
+            (&self.tname, &mut header.tname),
+            (&self.pexch, &mut header.pexch),
+            (&self.padr1, &mut header.padr1),
+            (&self.padr2, &mut header.padr2),
+            (&self.psect, &mut header.psect),
+            (&self.pclub, &mut header.pclub),
+            (&self.rname, &mut header.rname),
+            (&self.rcall, &mut header.rcall),
+            (&self.radr1, &mut header.radr1),
+            (&self.radr2, &mut header.radr2),
+            (&self.rpoco, &mut header.rpoco),
+            (&self.rcity, &mut header.rcity),
+            (&self.rcoun, &mut header.rcoun),
+            (&self.rphon, &mut header.rphon),
+            (&self.rhbbs, &mut header.rhbbs),
+            (&self.mope1, &mut header.mope1),
+            (&self.mope2, &mut header.mope2),
+            (&self.stxeq, &mut header.stxeq),
+            (&self.spowe, &mut header.spowe),
+            (&self.srxeq, &mut header.srxeq),
+            (&self.sante, &mut header.sante),
+            (&self.santh, &mut header.santh),
+        ];
+        for (value, header_field) in fields {
+            if let Some(value) = value {
+                *header_field = value.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::adif::AdifFile;
+use crate::reg1test::Reg1testHeader;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::fmt;
+
+// Built-in calendar of recurring IARU Region 1 VHF and up contests
+
+// Day on which a contest starts
+#[derive(Debug, Clone, Copy)]
+pub enum Schedule {
+    Yearly { month: u32, weekday: Weekday, nth: u8 }, // n-th weekday of the month every year
+    Monthly { weekday: Weekday, nth: u8 },            // n-th weekday of every month
+}
+
+// Time zone of contest start time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clock {
+    Utc,
+    UkLocal, // GMT, or BST in summer
+}
+
+#[derive(Debug)]
+pub struct Contest {
+    pub name: &'static str,              // contest name, used as TName
+    pub id: &'static str,                // short name used in file names
+    pub bands: &'static [&'static str],  // PBand values of the contest, empty for all bands
+    pub schedule: Schedule,
+    pub start: (u32, u32),               // start time: hour, minute
+    pub duration_minutes: i64,
+    pub clock: Clock,
+}
+
+const MICROWAVE_BANDS: [&str; 10] = [
+    "2,3 GHz", "3,4 GHz", "5,7 GHz", "10 GHz", "24 GHz", "47 GHz", "76 GHz", "122 GHz", "134 GHz",
+    "241 GHz",
+];
+
+pub static CONTESTS: [Contest; 12] = [
+    Contest {
+        name: "IARU Region 1 VHF Contest",
+        id: "IARU-VHF",
+        bands: &["144 MHz"],
+        schedule: Schedule::Yearly { month: 9, weekday: Weekday::Sat, nth: 1 },
+        start: (14, 0),
+        duration_minutes: 24 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "IARU Region 1 UHF/Microwave Contest",
+        id: "IARU-UHF",
+        bands: &[
+            "432 MHz", "1,3 GHz", "2,3 GHz", "3,4 GHz", "5,7 GHz", "10 GHz", "24 GHz", "47 GHz",
+            "76 GHz", "122 GHz", "134 GHz", "241 GHz",
+        ],
+        schedule: Schedule::Yearly { month: 10, weekday: Weekday::Sat, nth: 1 },
+        start: (14, 0),
+        duration_minutes: 24 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "Nordic Activity Contest 144 MHz",
+        id: "NAC",
+        bands: &["144 MHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 1 },
+        start: (17, 0),
+        duration_minutes: 4 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "Nordic Activity Contest 432 MHz",
+        id: "NAC",
+        bands: &["432 MHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 2 },
+        start: (17, 0),
+        duration_minutes: 4 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "Nordic Activity Contest 1,3 GHz",
+        id: "NAC",
+        bands: &["1,3 GHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 3 },
+        start: (17, 0),
+        duration_minutes: 4 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "Nordic Activity Contest Microwave",
+        id: "NAC",
+        bands: &MICROWAVE_BANDS,
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 4 },
+        start: (17, 0),
+        duration_minutes: 4 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "Nordic Activity Contest 50 MHz",
+        id: "NAC",
+        bands: &["50 MHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Thu, nth: 2 },
+        start: (17, 0),
+        duration_minutes: 4 * 60,
+        clock: Clock::Utc,
+    },
+    Contest {
+        name: "UK Activity Contest 144 MHz",
+        id: "UKAC",
+        bands: &["144 MHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 1 },
+        start: (20, 0),
+        duration_minutes: 150,
+        clock: Clock::UkLocal,
+    },
+    Contest {
+        name: "UK Activity Contest 432 MHz",
+        id: "UKAC",
+        bands: &["432 MHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 2 },
+        start: (20, 0),
+        duration_minutes: 150,
+        clock: Clock::UkLocal,
+    },
+    Contest {
+        name: "UK Activity Contest 1,3 GHz",
+        id: "UKAC",
+        bands: &["1,3 GHz"],
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 3 },
+        start: (20, 0),
+        duration_minutes: 150,
+        clock: Clock::UkLocal,
+    },
+    Contest {
+        name: "UK Activity Contest Microwave",
+        id: "UKAC",
+        bands: &MICROWAVE_BANDS,
+        schedule: Schedule::Monthly { weekday: Weekday::Tue, nth: 4 },
+        start: (20, 0),
+        duration_minutes: 150,
+        clock: Clock::UkLocal,
+    },
+    Contest {
+        name: "OK/OM VHF Activity Contest",
+        id: "OK-AKTIV",
+        bands: &[],
+        schedule: Schedule::Monthly { weekday: Weekday::Sun, nth: 3 },
+        start: (8, 0),
+        duration_minutes: 3 * 60,
+        clock: Clock::Utc,
+    },
+];
+
+impl Contest {
+    // Contest starts on this date
+    pub fn is_scheduled(&self, date: NaiveDate) -> bool {
+        let (month, weekday, nth) = match self.schedule {
+            Schedule::Yearly { month, weekday, nth } => (month, weekday, nth),
+            Schedule::Monthly { weekday, nth } => (date.month(), weekday, nth),
+        };
+        date.month() == month
+            && NaiveDate::from_weekday_of_month_opt(date.year(), month, weekday, nth) == Some(date)
+    }
+
+    // Contest is held on the PBand; HF and unknown bands are never part of a contest
+    pub fn has_band(&self, pband: &str) -> bool {
+        Reg1testHeader::is_known_band(pband) && (self.bands.is_empty() || self.bands.contains(&pband))
+    }
+
+    // Session of the contest starting on the date, None if the contest is not held then
+    pub fn session(&'static self, date: NaiveDate) -> Option<Session> {
+        if !self.is_scheduled(date) {
+            return None;
+        }
+        let (hour, minute) = self.start;
+        let mut start = date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?);
+        if self.clock == Clock::UkLocal && is_summer_time(date) {
+            start -= Duration::hours(1);
+        }
+        Some(Session {
+            contest: self,
+            start,
+            end: start + Duration::minutes(self.duration_minutes),
+            qsos: 0,
+        })
+    }
+}
+
+// European summer time from the last Sunday of March to the last Sunday of October
+fn is_summer_time(date: NaiveDate) -> bool {
+    let last_sunday = |month| {
+        NaiveDate::from_weekday_of_month_opt(date.year(), month, Weekday::Sun, 5)
+            .or(NaiveDate::from_weekday_of_month_opt(date.year(), month, Weekday::Sun, 4))
+    };
+    match (last_sunday(3), last_sunday(10)) {
+        (Some(begin), Some(end)) => date >= begin && date < end,
+        _ => false,
+    }
+}
+
+// One contest held on one date, with the number of QSOs made in it
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub contest: &'static Contest,
+    pub start: NaiveDateTime, // UTC
+    pub end: NaiveDateTime,   // UTC, QSOs from this time on are not part of the session
+    pub qsos: usize,
+}
+
+impl Session {
+    // Contest short name and start date, e.g. "NAC_20260106"
+    pub fn file_prefix(&self) -> String {
+        format!("{}_{}", self.contest.id, self.start.format("%Y%m%d"))
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} - {} UTC, {} QSO(s)",
+            self.contest.name,
+            self.start.format("%Y-%m-%d %H:%M"),
+            self.end.format("%Y-%m-%d %H:%M"),
+            self.qsos
+        )
+    }
+}
+
+// Contest sessions of the calendar the QSOs were made in, in order of start time.
+// A QSO may be part of several sessions if contests overlap.
+pub fn detect_sessions(adif: &AdifFile) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+
+    for record in adif.records.iter() {
+        let Some(time) = record.date_time() else {
+            continue;
+        };
+        let pband = record
+            .value("BAND")
+            .map(Reg1testHeader::get_band)
+            .filter(|pband| !pband.is_empty())
+            .or(record
+                .value("FREQ")
+                .and_then(|freq| freq.parse::<f64>().ok())
+                .map(Reg1testHeader::get_band_from_freq))
+            .unwrap_or_default();
+
+        for contest in CONTESTS.iter().filter(|contest| contest.has_band(&pband)) {
+            // Contests last up to 24 hours, so a QSO may be part of one started a day earlier
+            for date in [time.date() - Duration::days(1), time.date()] {
+                let Some(session) = contest.session(date) else {
+                    continue;
+                };
+                if time < session.start || time >= session.end {
+                    continue;
+                }
+                match sessions
+                    .iter_mut()
+                    .find(|s| std::ptr::eq(s.contest, contest) && s.start == session.start)
+                {
+                    Some(found) => found.qsos += 1,
+                    None => sessions.push(Session { qsos: 1, ..session }),
+                }
+            }
+        }
+    }
+
+    sessions.sort_by_key(|session| session.start);
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adi::read_adi;
+
+    fn contest(name: &str) -> &'static Contest {
+        CONTESTS.iter().find(|contest| contest.name == name).unwrap()
+    }
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn uk_contests_start_an_hour_earlier_in_summer() {
+        let ukac = contest("UK Activity Contest 144 MHz");
+        let winter = ukac.session(date("2026-01-06")).unwrap();
+        assert_eq!((winter.start, winter.end), (time("2026-01-06 20:00"), time("2026-01-06 22:30")));
+        let summer = ukac.session(date("2026-07-07")).unwrap();
+        assert_eq!((summer.start, summer.end), (time("2026-07-07 19:00"), time("2026-07-07 21:30")));
+    }
+
+    #[test]
+    fn summer_time_changes_on_last_sundays_of_march_and_october() {
+        let ukac = contest("UK Activity Contest Microwave");
+        assert_eq!(ukac.session(date("2026-03-24")).unwrap().start, time("2026-03-24 20:00"));
+        assert_eq!(ukac.session(date("2026-10-27")).unwrap().start, time("2026-10-27 20:00"));
+        let ukac = contest("UK Activity Contest 1,3 GHz");
+        assert_eq!(ukac.session(date("2026-10-20")).unwrap().start, time("2026-10-20 19:00"));
+    }
+
+    #[test]
+    fn utc_contests_keep_start_time() {
+        let nac = contest("Nordic Activity Contest 144 MHz");
+        assert_eq!(nac.session(date("2026-07-07")).unwrap().start, time("2026-07-07 17:00"));
+        assert!(nac.session(date("2026-07-14")).is_none());
+    }
+
+    #[test]
+    fn contest_of_24_hours_crosses_midnight() {
+        let iaru = contest("IARU Region 1 VHF Contest");
+        let session = iaru.session(date("2026-09-05")).unwrap();
+        assert_eq!((session.start, session.end), (time("2026-09-05 14:00"), time("2026-09-06 14:00")));
+        assert!(iaru.session(date("2026-09-06")).is_none());
+
+        let adif = read_adi(
+            "<EOH>\
+            <CALL:5>LY2AA <QSO_DATE:8>20260905 <TIME_ON:4>2300 <BAND:2>2m <EOR>\
+            <CALL:5>LY2AB <QSO_DATE:8>20260906 <TIME_ON:4>1359 <BAND:2>2m <EOR>\
+            <CALL:5>LY2AC <QSO_DATE:8>20260906 <TIME_ON:4>1400 <BAND:2>2m <EOR>",
+        )
+        .unwrap();
+        let sessions = detect_sessions(&adif);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].contest.name, iaru.name);
+        assert_eq!(sessions[0].qsos, 2);
+    }
+
+    #[test]
+    fn qsos_on_unknown_bands_are_not_part_of_a_contest() {
+        let adif = read_adi(
+            "<EOH>\
+            <CALL:5>DL1AA <QSO_DATE:8>20260118 <TIME_ON:4>0905 <BAND:3>20m <EOR>\
+            <CALL:5>DL1AB <QSO_DATE:8>20260118 <TIME_ON:4>0910 <FREQ:6>14.070 <EOR>",
+        )
+        .unwrap();
+        assert!(detect_sessions(&adif).is_empty());
+        assert!(!contest("OK/OM VHF Activity Contest").has_band(""));
+        assert!(contest("OK/OM VHF Activity Contest").has_band("432 MHz"));
+    }
+}
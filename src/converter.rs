@@ -1,346 +1,858 @@
 use crate::reg1test::Reg1testHeader;
+use crate::reg1test::Reg1testLog;
 use crate::reg1test::Reg1testQSORecord;
 use crate::reg1test::Reg1testQSOs;
 use crate::reg1test::Reg1testRemarks;
 
-use crate::utils::FoundCaptured;
-use crate::Rule;
-use crate::SKIP_REMARKS;
-use core::sync::atomic::Ordering;
-use std::error::Error;
+use crate::adif::{AdifField, AdifFile, AdifRecord};
+use crate::config::{ModeMapping, StationProfile};
+use crate::error::Error;
+use crate::locator::{self, Locator};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::collections::HashSet;
 // use std::str::FromStr;
 
-pub struct Band<'a> {
-    pub header: Reg1testHeader<'a>,
-    pub records: Reg1testQSOs<'a>, 
+// Format of ADIF input
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    #[default]
+    Adi,
+    Adx,
 }
 
-impl<'a> Band<'a> {
-    pub fn add_qso(&mut self, qso: Reg1testQSORecord<'a>) {
+// Settings of ADIF to REG1TEST conversion
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    pub input_format: InputFormat,
+    pub skip_remarks: bool,           // no remarks in EDI files
+    pub dupe_by_mode: bool,           // same call in another mode is not a duplicate
+    pub dupe_window: u32,             // minutes after which the same call may be worked again, 0 - never
+    pub profile: StationProfile,      // REG1TEST header values
+    pub start: Option<NaiveDateTime>, // contest start (UTC), earlier QSOs are skipped
+    pub end: Option<NaiveDateTime>,   // contest end (UTC), QSOs from this time on are skipped
+    pub contest_id: Option<String>,   // only QSOs with this CONTEST_ID
+    pub renumber: bool,               // sent QSO numbers from 001 in chronological order
+}
+
+// EDI file of one band: PBand, suffix of the file name and REG1TEST text
+#[derive(Debug, Clone)]
+pub struct BandOutput {
+    pub band: String,
+    pub file_suffix: String,
+    pub text: String,
+}
+
+impl BandOutput {
+    // EDI files of the logs. File suffix is the band, preceded by the station locator if
+    // logs of the same band differ in it and by the callsign if logs differ in it,
+    // e.g. "LY1XX-P_KO15AA_144MHz".
+    pub fn from_logs(logs: &[Reg1testLog]) -> Vec<BandOutput> {
+        logs.iter()
+            .map(|log| {
+                // "1,3 GHz" gives "1_3GHz"
+                let mut file_suffix = match log.header.pband.as_str() {
+                    "" => "unknown".to_string(),
+                    pband => pband.replace(',', "_").replace(' ', ""),
+                };
+                if logs.iter().any(|other| {
+                    other.header.pband == log.header.pband
+                        && !other.header.pwwlo.eq_ignore_ascii_case(&log.header.pwwlo)
+                }) {
+                    file_suffix = format!("{}_{}", log.header.pwwlo.to_ascii_uppercase(), file_suffix);
+                }
+                if logs.iter().any(|other| !other.header.pcall.eq_ignore_ascii_case(&log.header.pcall)) {
+                    // "LY1XX/P" gives "LY1XX-P"
+                    let pcall = log.header.pcall.to_ascii_uppercase().replace('/', "-");
+                    file_suffix = format!("{}_{}", pcall, file_suffix);
+                }
+                BandOutput {
+                    band: log.header.pband.clone(),
+                    file_suffix,
+                    text: log.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+pub struct Band {
+    pub header: Reg1testHeader,
+    pub records: Reg1testQSOs,
+    pub times: Vec<Option<NaiveDateTime>>, // start times of the QSOs with seconds, EDI keeps HHMM only
+    pub wwls: HashSet<String>,      // large squares (4 character locators) worked
+    pub dxccs: HashSet<String>,     // DXCC entities worked
+    pub exchanges: HashSet<String>, // received exchanges
+}
+
+impl Band {
+    pub fn new(header: Reg1testHeader) -> Self {
+        Band {
+            header,
+            records: Reg1testQSOs::default(),
+            times: Vec::new(),
+            wwls: HashSet::new(),
+            dxccs: HashSet::new(),
+            exchanges: HashSet::new(),
+        }
+    }
+
+    pub fn add_qso(&mut self, qso: Reg1testQSORecord, time: Option<NaiveDateTime>) {
         self.records.qso_records.push(qso);
+        self.times.push(time);
         self.records.count += 1;
     }
+
+    // Same call already worked on this band. If 'by_mode' is set, only a QSO in the
+    // same mode counts, and with a non-zero 'window_minutes' the call may be worked
+    // again once that many minutes have passed since the last valid QSO made at 'time'.
+    pub fn is_duplicate(
+        &self,
+        qso: &Reg1testQSORecord,
+        time: Option<NaiveDateTime>,
+        by_mode: bool,
+        window_minutes: u32,
+    ) -> bool {
+        let last_valid_qso = self.records.qso_records.iter().zip(self.times.iter()).rev().find(|(r, _)| {
+            r.duplicate_qso.is_empty()
+                && r.call.eq_ignore_ascii_case(&qso.call)
+                && (!by_mode || r.mode_code == qso.mode_code)
+        });
+        match last_valid_qso {
+            None => false,
+            Some(_) if window_minutes == 0 => true,
+            Some((_, last_time)) => match (last_time, time) {
+                (Some(t1), Some(t2)) => (t2 - *t1).num_seconds().abs() < window_minutes as i64 * 60,
+                _ => true,
+            },
+        }
+    }
+
+    // Fills claimed score lines of the header from valid (not duplicate) QSOs.
+    // Scoring is one point per km with no bonuses, band multiplier and other multipliers are 1.
+    pub fn set_claimed_score(&mut self) {
+        let valid_qsos: Vec<&Reg1testQSORecord> = self
+            .records
+            .qso_records
+            .iter()
+            .filter(|qso| qso.duplicate_qso.is_empty())
+            .collect();
+        let qso_points: u32 = valid_qsos.iter().filter_map(|qso| qso.qso_points).sum();
+
+        self.header.cqsos = format!("{};1", valid_qsos.len());
+        self.header.cqsop = qso_points.to_string();
+        self.header.cwwls = format!("{};0;1", self.wwls.len());
+        self.header.cwwlb = "0".to_string();
+        self.header.cexcs = format!("{};0;1", self.exchanges.len());
+        self.header.cexcb = "0".to_string();
+        self.header.cdxcs = format!("{};0;1", self.dxccs.len());
+        self.header.cdxcb = "0".to_string();
+        self.header.ctosc = qso_points.to_string();
+
+        // Best DX is the QSO with most points, distance in whole km
+        if let Some(odx) = valid_qsos
+            .iter()
+            .filter(|qso| qso.qso_points.is_some())
+            .max_by_key(|qso| qso.qso_points)
+            && let Some(km) = locator::distance(&self.header.pwwlo, &odx.received_wwl)
+        {
+            self.header.codxc = format!(
+                "{};{};{}",
+                odx.call.to_ascii_uppercase(),
+                odx.received_wwl.to_ascii_uppercase(),
+                km.trunc() as u32
+            );
+        }
+    }
+
+    // Sent QSO numbers in chronological order: reports numbers used more than once, numbers
+    // lower than the previous one and numbers missing from the series
+    pub fn check_serials(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut numbers: Vec<u16> = Vec::new();
+        for qso in self.records.qso_records.iter().filter(|qso| qso.sent_qso_number > 0) {
+            let label = format!("QSO with {} on {} {}", qso.call, qso.date, qso.time);
+            let number = qso.sent_qso_number;
+            if numbers.contains(&number) {
+                messages.push(format!("{}: sent QSO number {:03} is used again", label, number));
+            } else if let Some(previous) = numbers.last()
+                && number < *previous
+            {
+                messages.push(format!("{}: sent QSO number {:03} follows {:03}", label, number, previous));
+            }
+            numbers.push(number);
+        }
+
+        if let (Some(first), Some(last)) = (numbers.iter().min(), numbers.iter().max()) {
+            let missing: Vec<String> = (*first..=*last)
+                .filter(|number| !numbers.contains(number))
+                .map(|number| format!("{:03}", number))
+                .collect();
+            if !missing.is_empty() {
+                messages.push(format!("sent QSO numbers missing: {}", missing.join(", ")));
+            }
+        }
+        messages
+    }
+
+    // Sent QSO numbers from 001 in chronological order
+    pub fn renumber(&mut self) {
+        for (number, qso) in self.records.qso_records.iter_mut().enumerate() {
+            qso.sent_qso_number = number as u16 + 1;
+        }
+    }
+
+    // Marks the first QSO with a large square, DXCC entity or exchange on this band with "N"
+    pub fn set_new_multipliers(&mut self, qso: &mut Reg1testQSORecord, dxcc: &str) {
+        if let Some(large_square) = qso.received_wwl.get(..4)
+            && self.wwls.insert(large_square.to_ascii_uppercase())
+        {
+            qso.new_wwl = "N".to_string();
+        }
+        if !dxcc.is_empty() && self.dxccs.insert(dxcc.to_ascii_uppercase()) {
+            qso.new_dxcc = "N".to_string();
+        }
+        if !qso.received_exchange.is_empty()
+            && self.exchanges.insert(qso.received_exchange.to_ascii_uppercase())
+        {
+            qso.new_exchange = "N".to_string();
+        }
+    }
 }
 
+// Converts ADIF log to REG1TEST logs, one log per station callsign, band and station
+// locator in order of the first QSO
 pub fn convert_to_reg1test(
-    mut parse_result: pest::iterators::Pairs<'_, Rule>,
-) -> Result<String, Box<dyn Error>> {
+    adif: &AdifFile,
+    options: &ConvertOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<Reg1testLog>, Error> {
     let mut band_array: Vec<Band> = Vec::new();
 
     let mut r1t_header: Reg1testHeader = Reg1testHeader::default();
+    options.profile.apply_to(&mut r1t_header);
     let mut r1t_remarks = Reg1testRemarks::default();
-    let mut r1t_qso_records = Reg1testQSOs::default();
-    let mut r1t_record = Reg1testQSORecord::default();
-
-    let mut station_callsign = FoundCaptured::default();
-    let mut my_square = FoundCaptured::default();
 
     let mut min_date: u32 = 99991231; // extremely large date as number
     let mut min_date_str: &str = "";
     let mut max_date: u32 = 0; // extremely small date as number
     let mut max_date_str: &str = "";
 
-    let parsed_adi_rules = parse_result.next().unwrap();
-
-    for inner_pair in parsed_adi_rules.into_inner() {
-        //        println!("{:?}", inner_pair.as_str().trim());
-        match inner_pair.as_rule() {
-            Rule::header => {
-                // println!("{:?}", inner_pair.as_rule());
-                for inner_pair1 in inner_pair.into_inner() {
-                    match inner_pair1.as_rule() {
-                        Rule::adt_string => {
-                            // println!("{:?} {:?}", inner_pair1.as_rule(), inner_pair1.as_str());
-                            r1t_remarks.multi_line.push(inner_pair1.as_str().to_owned());
-                        }
-                        Rule::adt_multi_string => {
-                            // println!("{:?} {:?}", inner_pair1.as_rule(), inner_pair1.as_str());
-                            for inner_pair2 in inner_pair1.into_inner() {
-                                match inner_pair2.as_rule() {
-                                    Rule::adt_string => {
-                                        // println!("{:?} {:?}", inner_pair2.as_rule(), inner_pair2.as_str());
-                                        r1t_remarks
-                                            .multi_line
-                                            .push(inner_pair2.as_str().to_owned());
-                                    }
-                                    _ => continue,
-                                }
-                            }
-                        }
-                        Rule::data_specifier => {
-                            // println!("{:?} {:?}", inner_pair1.as_rule(), inner_pair1.as_str());
-                            let mut adif_version = FoundCaptured::default();
-                            let mut created_timestamp = FoundCaptured::default();
-                            let mut program_id = FoundCaptured::default();
-                            let mut program_version = FoundCaptured::default();
-
-                            for inner_pair2 in inner_pair1.into_inner() {
-                                match inner_pair2.as_rule() {
-                                    Rule::field => {
-                                        // println!("{:?} {:?}", inner_pair2.as_rule(), inner_pair2.as_str());
-                                        for inner_pair3 in inner_pair2.into_inner() {
-                                            match inner_pair3.as_rule() {
-                                                Rule::field_name => {
-                                                    // println!("{:?} {:?}", inner_pair3.as_rule(), inner_pair3.as_str());
-                                                    match inner_pair3
-                                                        .as_str()
-                                                        .to_uppercase()
-                                                        .as_str()
-                                                    {
-                                                        "ADIF_VER" => {
-                                                            adif_version.set_found();
-                                                        }
-                                                        "CREATED_TIMESTAMP" => {
-                                                            created_timestamp.set_found();
-                                                        }
-                                                        "PROGRAMID" => {
-                                                            program_id.set_found();
-                                                        }
-                                                        "PROGRAMVERSION" => {
-                                                            program_version.set_found();
-                                                        }
-                                                        _ => continue,
-                                                    }
-                                                }
-                                                Rule::data_length => {
-                                                    let len: usize =
-                                                        inner_pair3.as_str().parse().unwrap();
-                                                    // println!("{:?} {:?} {}", inner_pair3.as_rule(), inner_pair3.as_str(), len);
-                                                    adif_version.set_length(len);
-                                                    created_timestamp.set_length(len);
-                                                    program_id.set_length(len);
-                                                    program_version.set_length(len);
-                                                }
-                                                _ => continue,
-                                            }
-                                        }
-                                    }
-                                    Rule::data => {
-                                        let data_as_string =
-                                            inner_pair2.as_str().trim().to_string();
-                                        // println!("{:?} {:?} {}", inner_pair2.as_rule(), inner_pair2.as_str(), data_as_string);
-                                        if adif_version.is_found() {
-                                            let text_line = format!("ADIF_VER={}", data_as_string);
-                                            r1t_remarks.multi_line.push(text_line);
-                                        }
-                                        if created_timestamp.is_found() {
-                                            let text_line =
-                                                format!("CREATED_TIMESTAMP={}", data_as_string);
-                                            r1t_remarks.multi_line.push(text_line);
-                                        }
-                                        if program_id.is_found() {
-                                            let text_line = format!("PROGRAMID={}", data_as_string);
-                                            r1t_remarks.multi_line.push(text_line);
-                                        }
-                                        if program_version.is_found() {
-                                            let text_line =
-                                                format!("PROGRAMVERSION={}", data_as_string);
-                                            r1t_remarks.multi_line.push(text_line);
-                                        }
-                                    }
-                                    _ => continue,
-                                }
-                            }
-                        }
-                        _ => continue,
-                    }
-                }
-            }
+    // Free text of the ADIF header
+    for text_line in adif.header.preamble.lines() {
+        if !text_line.trim().is_empty() {
+            r1t_remarks.multi_line.push(text_line.trim_end().to_owned());
+        }
+    }
+
+    // ADIF header fields copied to remarks
+    for field_name in ["ADIF_VER", "CREATED_TIMESTAMP", "PROGRAMID", "PROGRAMVERSION"] {
+        if let Some(data) = adif.header.value(field_name) {
+            r1t_remarks.multi_line.push(format!("{}={}", field_name, data));
+        }
+    }
 
-            Rule::record => {
-                // println!("{:?}", inner_pair.as_str().trim());
-                let mut call = FoundCaptured::default();
-                let mut band = FoundCaptured::default();  // was missing?
-                let mut qso_date = FoundCaptured::default();
-                let mut time = FoundCaptured::default();
-                let mut mode = FoundCaptured::default();
-                let mut rst_sent = FoundCaptured::default();
-                let mut stx = FoundCaptured::default();
-                let mut rst_rcvd = FoundCaptured::default();
-                let mut srx = FoundCaptured::default();
-                let mut gridsquare = FoundCaptured::default();
-
-                r1t_qso_records.count += 1;
-
-                for inner_pair1 in inner_pair.into_inner() {
-                    // println!("{:?} {:?}", inner_pair1.as_rule(), inner_pair1.as_str());
-                    for inner_pair2 in inner_pair1.into_inner() {
-                        // println!("{:?} {:?}", inner_pair2.as_rule(), inner_pair2.as_str());
-                        match inner_pair2.as_rule() {
-                            Rule::field => {
-                                for inner_pair3 in inner_pair2.into_inner() {
-                                    // println!("{:?} {:?}", inner_pair3.as_rule(), inner_pair3.as_str());
-                                    match inner_pair3.as_rule() {
-                                        Rule::field_name => {
-                                            match inner_pair3.as_str().to_uppercase().as_str() {
-                                                "STATION_CALLSIGN" => {
-                                                    station_callsign.set_found();
-                                                }
-                                                "QSO_DATE" => {
-                                                    qso_date.set_found();
-                                                }
-                                                "TIME_ON" => {
-                                                    time.set_found();
-                                                }
-                                                "BAND" => {
-                                                    band.set_found();
-                                                }
-                                                "CALL" => {
-                                                    call.set_found();
-                                                }
-                                                "MODE" => {
-                                                    mode.set_found();
-                                                }
-                                                "RST_SENT" => {
-                                                    rst_sent.set_found();
-                                                }
-                                                "STX" => {
-                                                    stx.set_found();
-                                                }
-                                                "RST_RCVD" => {
-                                                    rst_rcvd.set_found();
-                                                }
-                                                "SRX" => {
-                                                    srx.set_found();
-                                                }
-                                                "GRIDSQUARE" => {
-                                                    gridsquare.set_found();
-                                                }
-                                                "MY_GRIDSQUARE" => {
-                                                    my_square.set_found();
-                                                }
-                                                _ => continue,
-                                            }
-                                        }
-                                        Rule::data_length => {
-                                            let len: usize = inner_pair3.as_str().parse().unwrap();
-                                            // println!("{:?} {:?} {}", inner_pair3.as_rule(), inner_pair3.as_str(), len);
-                                            station_callsign.set_length(len);
-                                            qso_date.set_length(len);
-                                            time.set_length(len);
-                                            band.set_length(len);
-                                            call.set_length(len);
-                                            mode.set_length(len);
-                                            rst_sent.set_length(len);
-                                            stx.set_length(len);
-                                            rst_rcvd.set_length(len);
-                                            srx.set_length(len);
-                                            gridsquare.set_length(len);
-                                            my_square.set_length(len);
-                                        }
-                                        _ => continue,
-                                    }
-                                }
-                            }
-                            Rule::data => {
-                                let data_as_str = inner_pair2.as_str().trim();
-                                // println!("{:?} {:?} {}", inner_pair2.as_rule(), inner_pair2.as_str(), data_as_str);
-                                if station_callsign.is_found() {
-                                    r1t_header.pcall = data_as_str;
-                                }
-                                if qso_date.is_found() {
-                                    let date_as_number: u32 = data_as_str.parse().unwrap(); // string to number
-                                    if date_as_number < min_date {
-                                        // set MIN qso date
-                                        min_date = date_as_number;
-                                        min_date_str = data_as_str;
-                                    }
-                                    if date_as_number > max_date {
-                                        // set MAX qso date
-                                        max_date = date_as_number;
-                                        max_date_str = data_as_str;
-                                    }
-                                    r1t_record.date = &data_as_str[2..];
-                                }
-                                if time.is_found() {
-                                    if data_as_str.len() > 4 {
-                                        r1t_record.time = &data_as_str[0..4];
-                                    } else {
-                                        r1t_record.time = data_as_str;
-                                    }
-                                }
-                                if band.is_found() {
-                                    r1t_header.pband = Reg1testHeader::get_band(data_as_str);
-                                }
-                                if call.is_found() {
-                                    r1t_record.call = data_as_str.into();
-                                }
-                                if mode.is_found() {
-                                    // Regex string for RST: r"([12345][123456789])([123456789asm])*"
-                                    r1t_record.mode_code =
-                                        Reg1testQSORecord::get_mode(inner_pair2.as_str());
-                                }
-                                if rst_sent.is_found() {
-                                    r1t_record.sent_rst = data_as_str.into();
-                                }
-                                if stx.is_found() {
-                                    let number: u16 = data_as_str.trim_start_matches('0').parse().unwrap(); // string to number
-                                    r1t_record.sent_qso_number = number;
-                                }
-                                if rst_rcvd.is_found() {
-                                    r1t_record.received_rst = data_as_str.into();
-                                }
-                                if srx.is_found() {
-                                    let number: u16 = data_as_str.trim_start_matches('0').parse().unwrap(); // string to number
-                                    r1t_record.received_qso_number = number;
-                                }
-                                if gridsquare.is_found() {
-                                    r1t_record.received_wwl = data_as_str;
-                                }
-                                if my_square.is_found() {
-                                    r1t_header.pwwlo = data_as_str;
-                                }
-                            }
-                            _ => continue,
-                        }
-                    }
+    // QSOs in chronological order, QSOs without date and time last
+    let mut records: Vec<&AdifRecord> = adif
+        .records
+        .iter()
+        .filter(|record| is_contest_qso(record, options, warnings))
+        .collect();
+    records.sort_by_key(|record| {
+        let date_time = record.date_time();
+        (date_time.is_none(), date_time)
+    });
+
+    for record in records {
+        let mut r1t_record = Reg1testQSORecord::default();
+
+        match record.value("STATION_CALLSIGN") {
+            Some(station_callsign) => r1t_header.pcall = station_callsign.to_string(),
+            None if r1t_header.pcall.is_empty() => (),
+            None => warnings.push(format!(
+                "{}: no STATION_CALLSIGN, using {}",
+                qso_label(record),
+                r1t_header.pcall
+            )),
+        }
+        if let Some(qso_date) = record.value("QSO_DATE") {
+            if qso_date.len() != 8 || NaiveDate::parse_from_str(qso_date, "%Y%m%d").is_err() {
+                return Err(Error::Conversion(format!(
+                    "{}: invalid QSO_DATE '{}'",
+                    qso_label(record),
+                    qso_date
+                )));
+            }
+            let date_as_number: u32 = qso_date.parse().unwrap(); // string to number, checked above
+            if date_as_number < min_date {
+                // set MIN qso date
+                min_date = date_as_number;
+                min_date_str = qso_date;
+            }
+            if date_as_number > max_date {
+                // set MAX qso date
+                max_date = date_as_number;
+                max_date_str = qso_date;
+            }
+            r1t_record.date = qso_date[2..].to_string();
+        }
+        if let Some(time_on) = record.value("TIME_ON") {
+            if !matches!(time_on.len(), 4 | 6) || !time_on.chars().all(|c| c.is_ascii_digit()) {
+                return Err(Error::Conversion(format!(
+                    "{}: invalid TIME_ON '{}'",
+                    qso_label(record),
+                    time_on
+                )));
+            }
+            if time_on.len() > 4 {
+                r1t_record.time = time_on[0..4].to_string();
+            } else {
+                r1t_record.time = time_on.to_string();
+            }
+        }
+        r1t_header.pband = get_record_band(record, warnings);
+        if let Some(call) = record.value("CALL") {
+            r1t_record.call = call.to_string();
+        }
+        if let Some(rst_sent) = record.value("RST_SENT") {
+            r1t_record.sent_rst = parse_rst(record, "RST_SENT", rst_sent, warnings);
+        }
+        if let Some(stx) = record.value("STX") {
+            r1t_record.sent_qso_number = parse_qso_number(record, "STX", stx)?;
+        }
+        if let Some(rst_rcvd) = record.value("RST_RCVD") {
+            r1t_record.received_rst = parse_rst(record, "RST_RCVD", rst_rcvd, warnings);
+        }
+        if let Some(srx) = record.value("SRX") {
+            r1t_record.received_qso_number = parse_qso_number(record, "SRX", srx)?;
+        }
+        r1t_record.mode_code = Reg1testQSORecord::get_mode(
+            record.value("MODE").unwrap_or(""),
+            record.value("SUBMODE").unwrap_or(""),
+            &r1t_record.sent_rst,
+            &r1t_record.received_rst,
+        );
+        if let Some(ModeMapping::Code(mode_code)) = options
+            .profile
+            .mode_mapping(record.value("MODE").unwrap_or(""), record.value("SUBMODE").unwrap_or(""))
+        {
+            r1t_record.mode_code = mode_code;
+        }
+        // Stations on a locator boundary are logged with VUCC_GRIDS, the first square is used
+        if let Some(locator) = get_record_locator(record, "GRIDSQUARE", "LAT", "LON", warnings)
+            .or_else(|| get_vucc_grids(record, "VUCC_GRIDS", warnings).into_iter().next())
+        {
+            if locator.is_square() {
+                warnings.push(format!("{}: locator {} has 4 characters only", qso_label(record), locator));
+            }
+            r1t_record.received_wwl = locator.to_edi();
+        }
+        if let Some(srx_string) = record.value("SRX_STRING") {
+            // Received exchange is the part of SRX_STRING other than serial number, locator and RST
+            if let Some(exchange) = srx_string.split_whitespace().find(|token| {
+                token.trim_start_matches('0').parse::<u16>().ok() != Some(r1t_record.received_qso_number)
+                    && token.parse::<Locator>().is_err()
+                    && !is_rst(token)
+            }) {
+                if exchange.chars().count() > 6 {
+                    warnings.push(format!(
+                        "{}: received exchange {} is longer than 6 characters, skipped",
+                        qso_label(record),
+                        exchange
+                    ));
+                } else {
+                    r1t_record.received_exchange = exchange.to_string();
                 }
-                r1t_qso_records.qso_records.push(r1t_record);
-                // println!("{:?}", r1t_record);
-
-                let mut band_found = false;
-                for band in band_array.iter_mut() {
-                    if band.header.pband == r1t_header.pband {
-                        band.add_qso(r1t_record);
-                        // band.records.qso_records.push(r1t_record);
-                        // band.records.count += 1; 
-                        band_found = true;
-                        break
-                    }
+            }
+        }
+        let my_locator = match get_record_locator(record, "MY_GRIDSQUARE", "MY_LAT", "MY_LON", warnings) {
+            Some(locator) => Some(locator),
+            None => {
+                // EDI file has one PWWLo, so the station must not be on several squares
+                let my_vucc_grids = get_vucc_grids(record, "MY_VUCC_GRIDS", warnings);
+                if my_vucc_grids.len() > 1 {
+                    return Err(Error::Conversion(format!(
+                        "{}: MY_VUCC_GRIDS {} has several squares, but EDI file has one PWWLo; \
+                         set MY_GRIDSQUARE of the QSO",
+                        qso_label(record),
+                        record.value("MY_VUCC_GRIDS").unwrap_or("")
+                    )));
                 }
-                if band_found == false {
-                    let mut new_band = Band {
-                        header: Reg1testHeader::default(),
-                        records: Reg1testQSOs::default()
-                    };
-                    new_band.header = r1t_header.clone();
-                    new_band.add_qso(r1t_record);
-                    // new_band.records.qso_records.push(r1t_record);
-                    // new_band.records.count += 1;
-                    band_array.push(new_band);
+                my_vucc_grids.into_iter().next()
+            }
+        };
+        match my_locator {
+            Some(locator) => {
+                if locator.is_square() {
+                    warnings.push(format!(
+                        "{}: station locator {} has 4 characters only, PWWLo should have 6",
+                        qso_label(record),
+                        locator
+                    ));
                 }
+                r1t_header.pwwlo = locator.to_edi();
+            }
+            None if r1t_header.pwwlo.is_empty() => {
+                warnings.push(format!("{}: no MY_GRIDSQUARE", qso_label(record)));
+            }
+            None => warnings.push(format!(
+                "{}: no MY_GRIDSQUARE, using {}",
+                qso_label(record),
+                r1t_header.pwwlo
+            )),
+        }
+        // Points column must not be empty, QSO without both locators scores nothing
+        r1t_record.qso_points = match locator::qso_points(&r1t_header.pwwlo, &r1t_record.received_wwl) {
+            Some(points) => Some(points),
+            None => {
+                warnings.push(format!("{}: no locator to calculate distance, 0 QSO points", qso_label(record)));
+                Some(0)
             }
-            Rule::EOI => (),
-            _ => unreachable!(),
+        };
+
+        let band_index = match band_array
+            .iter()
+            .position(|band| {
+                // Separate log for every locator and callsign of rover or multi-station logs
+                band.header.pband == r1t_header.pband
+                    && band.header.pwwlo.eq_ignore_ascii_case(&r1t_header.pwwlo)
+                    && band.header.pcall.eq_ignore_ascii_case(&r1t_header.pcall)
+            })
+        {
+            Some(i) => i,
+            None => {
+                band_array.push(Band::new(r1t_header.clone()));
+                band_array.len() - 1
+            }
+        };
+        let band = &mut band_array[band_index];
+
+        if band.is_duplicate(&r1t_record, record.date_time(), options.dupe_by_mode, options.dupe_window) {
+            r1t_record.duplicate_qso = "D".to_string();
+            r1t_record.qso_points = Some(0);
+        } else {
+            let dxcc = record.value("DXCC").or(record.value("COUNTRY")).unwrap_or("");
+            band.set_new_multipliers(&mut r1t_record, dxcc);
         }
+        band.add_qso(r1t_record, record.date_time());
     }
 
-    let mut pdate: String = min_date_str.to_string();
-    pdate.push_str(";");
-    pdate.push_str(max_date_str);
-    r1t_header.tdate = pdate.as_str();
+    if band_array.is_empty() {
+        return Err(Error::Conversion("no QSOs in contest period or with CONTEST_ID".to_string()));
+    }
 
-    let mut reg1test_result = String::new();
+    // Contest period if specified, otherwise dates of first and last QSO
+    let start_date = match options.start {
+        Some(start) => start.format("%Y%m%d").to_string(),
+        None => min_date_str.to_string(),
+    };
+    let end_date = match options.end {
+        Some(end) => (end - Duration::minutes(1)).format("%Y%m%d").to_string(),
+        None => max_date_str.to_string(),
+    };
+    let pdate = format!("{};{}", start_date, end_date);
+    if options.skip_remarks {
+        r1t_remarks.multi_line.clear()
+    };
 
-    for band in band_array.iter_mut() {
-        band.header.tdate = pdate.as_str();
+    // Logs of every station callsign together, in order of the first QSO of the callsign
+    let mut callsigns: Vec<String> = Vec::new();
+    for band in band_array.iter() {
+        let pcall = band.header.pcall.to_ascii_uppercase();
+        if !callsigns.contains(&pcall) {
+            callsigns.push(pcall);
+        }
+    }
+    band_array.sort_by_key(|band| {
+        callsigns
+            .iter()
+            .position(|pcall| pcall.eq_ignore_ascii_case(&band.header.pcall))
+    });
 
-        if SKIP_REMARKS.load(Ordering::Relaxed) {
-            r1t_remarks.multi_line.clear()
-        };
+    let mut reg1test_logs: Vec<Reg1testLog> = Vec::new();
+    for mut band in band_array {
+        band.header.tdate = pdate.clone();
+        if options.renumber {
+            band.renumber();
+        } else {
+            for message in band.check_serials() {
+                warnings.push(format!("{} {}: {}", band.header.pcall, band.header.pband, message));
+            }
+        }
+        band.set_claimed_score();
+        reg1test_logs.push(Reg1testLog {
+            header: band.header,
+            remarks: r1t_remarks.clone(),
+            records: band.records,
+        });
+    }
+    Ok(reg1test_logs)
+}
+
+// Converts REG1TEST log back to ADIF, one record per QSO row
+pub fn convert_to_adif(reg1test_log: &Reg1testLog, warnings: &mut Vec<String>) -> AdifFile {
+    let mut adif = AdifFile::default();
+    let header = &reg1test_log.header;
+
+    adif.header.preamble = "\
+        Converted from REG1TEST using 'adi2edi' converter\n\
+        (https://github.com/ZilvinasJonaitis/adi2edi)"
+        .to_string();
+    for (field_name, data) in [
+        ("ADIF_VER", "3.1.4"),
+        ("PROGRAMID", "adi2edi"),
+        ("PROGRAMVERSION", env!("CARGO_PKG_VERSION")),
+    ] {
+        adif.header.fields.insert(AdifField::new(field_name, data, None));
+    }
+
+    let band = Reg1testHeader::get_adif_band(&header.pband);
+    if band.is_empty() {
+        warnings.push(format!("unknown PBand '{}', BAND is not set", header.pband));
+    }
+
+    for qso in reg1test_log.records.qso_records.iter() {
+        let qso_date = NaiveDate::parse_from_str(&qso.date, "%y%m%d")
+            .map(|date| date.format("%Y%m%d").to_string())
+            .unwrap_or_else(|_| {
+                warnings.push(format!("QSO with {}: invalid date '{}'", qso.call, qso.date));
+                "".to_string()
+            });
+        let number = |n: u16| if n > 0 { format!("{:03}", n) } else { "".to_string() };
+
+        let fields = [
+            ("CALL", qso.call.to_ascii_uppercase()),
+            ("QSO_DATE", qso_date),
+            ("TIME_ON", qso.time.to_string()),
+            ("BAND", band.clone()),
+            ("MODE", Reg1testQSORecord::get_adif_mode(qso.mode_code).to_string()),
+            ("RST_SENT", qso.sent_rst.to_string()),
+            ("STX", number(qso.sent_qso_number)),
+            ("RST_RCVD", qso.received_rst.to_string()),
+            ("SRX", number(qso.received_qso_number)),
+            ("SRX_STRING", qso.received_exchange.to_string()),
+            ("GRIDSQUARE", qso.received_wwl.to_ascii_uppercase()),
+            ("MY_GRIDSQUARE", header.pwwlo.to_ascii_uppercase()),
+            ("STATION_CALLSIGN", header.pcall.to_ascii_uppercase()),
+        ];
+
+        let mut record = AdifRecord::default();
+        for (field_name, data) in fields {
+            if !data.is_empty() {
+                record.fields.insert(AdifField::new(field_name, &data, None));
+            }
+        }
+        adif.records.push(record);
+    }
+    adif
+}
+
+// Short QSO description for warnings
+pub(crate) fn qso_label(record: &AdifRecord) -> String {
+    format!(
+        "QSO with {} on {} {}",
+        record.value("CALL").unwrap_or("?"),
+        record.value("QSO_DATE").unwrap_or("?"),
+        record.value("TIME_ON").unwrap_or("?")
+    )
+}
+
+// QSO has the CONTEST_ID and was made in the contest period, if these are specified,
+// and its mode is not excluded in the station profile
+pub fn is_contest_qso(record: &AdifRecord, options: &ConvertOptions, warnings: &mut Vec<String>) -> bool {
+    if options
+        .profile
+        .mode_mapping(record.value("MODE").unwrap_or(""), record.value("SUBMODE").unwrap_or(""))
+        == Some(ModeMapping::Exclude)
+    {
+        return false;
+    }
+    if let Some(contest_id) = &options.contest_id
+        && !record
+            .value("CONTEST_ID")
+            .is_some_and(|id| id.eq_ignore_ascii_case(contest_id))
+    {
+        return false;
+    }
+    if options.start.is_none() && options.end.is_none() {
+        return true;
+    }
+    match record.date_time() {
+        Some(time) => {
+            options.start.is_none_or(|start| time >= start) && options.end.is_none_or(|end| time < end)
+        }
+        None => {
+            warnings.push(format!("{}: no valid QSO_DATE and TIME_ON, QSO skipped", qso_label(record)));
+            false
+        }
+    }
+}
+
+// Signal report from RST_SENT or RST_RCVD field; reports of digital modes in dB, e.g. "-10",
+// do not fit REG1TEST RST columns and are left out
+fn parse_rst(record: &AdifRecord, field_name: &str, data: &str, warnings: &mut Vec<String>) -> String {
+    if matches!(data.len(), 2 | 3) && data.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return data.to_string();
+    }
+    warnings.push(format!("{}: {} '{}' is not an RST, left out", qso_label(record), field_name, data));
+    "".to_string()
+}
+
+// Token looks like a signal report, e.g. "59", "599" or "59A"
+fn is_rst(token: &str) -> bool {
+    let chars: Vec<char> = token.chars().collect();
+    matches!(chars.len(), 2 | 3)
+        && ('1'..='5').contains(&chars[0])
+        && ('1'..='9').contains(&chars[1])
+        && chars.get(2).is_none_or(|c| c.is_ascii_alphanumeric())
+}
+
+// Serial number from STX or SRX field
+fn parse_qso_number(record: &AdifRecord, field_name: &str, data: &str) -> Result<u16, Error> {
+    data.parse().map_err(|_| {
+        Error::Conversion(format!(
+            "{}: invalid {} '{}'",
+            qso_label(record),
+            field_name,
+            data
+        ))
+    })
+}
+
+// Locator from a gridsquare field, or from latitude and longitude fields if the gridsquare
+// is missing or invalid
+fn get_record_locator(
+    record: &AdifRecord,
+    grid_field: &str,
+    lat_field: &str,
+    lon_field: &str,
+    warnings: &mut Vec<String>,
+) -> Option<Locator> {
+    let gridsquare = record
+        .value(grid_field)
+        .and_then(|gridsquare| match gridsquare.parse::<Locator>() {
+            Ok(locator) => Some(locator),
+            Err(err) => {
+                warnings.push(format!("{}: {} skipped, {}", qso_label(record), grid_field, err));
+                None
+            }
+        });
+    let position = match (record.value(lat_field), record.value(lon_field)) {
+        (Some(lat), Some(lon)) => {
+            let locator = locator::parse_location(lat, true)
+                .zip(locator::parse_location(lon, false))
+                .and_then(|(lat, lon)| locator::coordinates_to_locator(lat, lon));
+            if locator.is_none() {
+                warnings.push(format!(
+                    "{}: {} '{}' and {} '{}' skipped, location should be XDDD MM.MMM",
+                    qso_label(record),
+                    lat_field,
+                    lat,
+                    lon_field,
+                    lon
+                ));
+            }
+            locator
+        }
+        _ => None,
+    };
+
+    match (gridsquare, position) {
+        (Some(gridsquare), Some(position)) => {
+            if !position.as_str().starts_with(&gridsquare.to_edi()) {
+                warnings.push(format!(
+                    "{}: {} {} does not match {} and {} ({}), using {}",
+                    qso_label(record),
+                    grid_field,
+                    gridsquare,
+                    lat_field,
+                    lon_field,
+                    position,
+                    grid_field
+                ));
+            }
+            Some(gridsquare)
+        }
+        (gridsquare, position) => gridsquare.or(position),
+    }
+}
+
+// Squares of VUCC_GRIDS or MY_VUCC_GRIDS field, e.g. "KO14,KO15"; invalid squares are skipped
+fn get_vucc_grids(record: &AdifRecord, field: &str, warnings: &mut Vec<String>) -> Vec<Locator> {
+    let Some(grids) = record.value(field) else {
+        return Vec::new();
+    };
+    grids
+        .split(',')
+        .filter(|grid| !grid.trim().is_empty())
+        .filter_map(|grid| match grid.parse::<Locator>() {
+            Ok(locator) => Some(locator),
+            Err(err) => {
+                warnings.push(format!("{}: {} square skipped, {}", qso_label(record), field, err));
+                None
+            }
+        })
+        .collect()
+}
+
+// PBand of the QSO from BAND, or from FREQ if BAND is missing or unknown
+fn get_record_band(record: &AdifRecord, warnings: &mut Vec<String>) -> String {
+    let band = record
+        .value("BAND")
+        .map(Reg1testHeader::get_band)
+        .filter(|pband| !pband.is_empty());
+    let band_from_freq = record
+        .value("FREQ")
+        .and_then(|freq| freq.parse::<f64>().ok())
+        .map(Reg1testHeader::get_band_from_freq)
+        .filter(|pband| !pband.is_empty());
+
+    match (band, band_from_freq) {
+        (Some(band), Some(band_from_freq)) => {
+            if band != band_from_freq {
+                warnings.push(format!(
+                    "{}: BAND {} does not match FREQ {}, using BAND",
+                    qso_label(record),
+                    record.value("BAND").unwrap_or(""),
+                    record.value("FREQ").unwrap_or("")
+                ));
+            }
+            band
+        }
+        (Some(band), None) => band,
+        (None, Some(band_from_freq)) => band_from_freq,
+        (None, None) => {
+            warnings.push(format!("{}: no known BAND or FREQ", qso_label(record)));
+            "".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qso(call: &str, mode_code: char, time: &str) -> Reg1testQSORecord {
+        Reg1testQSORecord {
+            date: "260106".to_string(),
+            time: time.get(..4).unwrap().to_string(),
+            call: call.to_string(),
+            mode_code,
+            ..Reg1testQSORecord::default()
+        }
+    }
+
+    fn time(text: &str) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&format!("20260106{}", text), "%Y%m%d%H%M%S").ok()
+    }
+
+    fn band_with(qsos: &[(&str, char, &str)]) -> Band {
+        let mut band = Band::new(Reg1testHeader::default());
+        for (call, mode_code, hhmmss) in qsos {
+            band.add_qso(qso(call, *mode_code, hhmmss), time(hhmmss));
+        }
+        band
+    }
 
-        reg1test_result.push_str(format!("{}\n{}\n{}\n", band.header, r1t_remarks, band.records).as_ref())
+    #[test]
+    fn same_call_is_duplicate_in_any_mode() {
+        let band = band_with(&[("OH1AA", '1', "180000")]);
+        assert!(band.is_duplicate(&qso("oh1aa", '2', "190000"), time("190000"), false, 0));
+        assert!(!band.is_duplicate(&qso("OH1AB", '1', "190000"), time("190000"), false, 0));
+    }
+
+    #[test]
+    fn same_call_in_another_mode_is_not_duplicate_by_mode() {
+        let band = band_with(&[("OH1AA", '1', "180000")]);
+        assert!(!band.is_duplicate(&qso("OH1AA", '2', "180500"), time("180500"), true, 0));
+        assert!(band.is_duplicate(&qso("OH1AA", '1', "180500"), time("180500"), true, 0));
+    }
+
+    #[test]
+    fn same_call_is_allowed_again_after_window() {
+        let band = band_with(&[("OH1AA", '1', "180050")]);
+        // 9 minutes 20 seconds, although minutes of TIME_ON differ by 10
+        assert!(band.is_duplicate(&qso("OH1AA", '1', "181010"), time("181010"), false, 10));
+        assert!(!band.is_duplicate(&qso("OH1AA", '1', "181050"), time("181050"), false, 10));
+        assert!(band.is_duplicate(&qso("OH1AA", '1', "181050"), None, false, 10));
+    }
+
+    #[test]
+    fn window_counts_from_last_valid_qso() {
+        let mut band = band_with(&[("OH1AA", '1', "180000")]);
+        let mut dupe = qso("OH1AA", '1', "180500");
+        dupe.duplicate_qso = "D".to_string();
+        band.add_qso(dupe, time("180500"));
+        assert!(!band.is_duplicate(&qso("OH1AA", '1', "181000"), time("181000"), false, 10));
+    }
+
+    fn new_flags(qso: &Reg1testQSORecord) -> (&str, &str, &str) {
+        (&qso.new_wwl, &qso.new_dxcc, &qso.new_exchange)
+    }
+
+    #[test]
+    fn first_large_square_dxcc_and_exchange_are_new() {
+        let mut band = Band::new(Reg1testHeader::default());
+        let mut first = qso("OH1AA", '1', "180000");
+        first.received_wwl = "KP20LE".to_string();
+        first.received_exchange = "VIL".to_string();
+        band.set_new_multipliers(&mut first, "224");
+        assert_eq!(new_flags(&first), ("N", "N", "N"));
 
+        // Same large square in another subsquare, same DXCC and exchange in another case
+        let mut second = qso("OH1AB", '1', "180100");
+        second.received_wwl = "kp20aa".to_string();
+        second.received_exchange = "vil".to_string();
+        band.set_new_multipliers(&mut second, "224");
+        assert_eq!(new_flags(&second), ("", "", ""));
     }
-    Ok(reg1test_result)
-}
\ No newline at end of file
+
+    #[test]
+    fn missing_locator_dxcc_and_exchange_are_not_new() {
+        let mut band = Band::new(Reg1testHeader::default());
+        let mut qso = qso("OH1AA", '1', "180000");
+        band.set_new_multipliers(&mut qso, "");
+        assert_eq!(new_flags(&qso), ("", "", ""));
+        assert!(band.wwls.is_empty() && band.dxccs.is_empty() && band.exchanges.is_empty());
+    }
+
+    #[test]
+    fn claimed_score_counts_valid_qsos_only() {
+        let mut band = Band::new(Reg1testHeader { pwwlo: "KO24PR".to_string(), ..Reg1testHeader::default() });
+        for (call, wwl, dxcc, duplicate) in [
+            ("OH1AA", "KP20LE", "224", false),
+            ("sm5bb", "jo89aa", "284", false),
+            ("OH1AA", "KP20LE", "224", true),
+        ] {
+            let mut qso = qso(call, '1', "180000");
+            qso.received_wwl = wwl.to_string();
+            qso.qso_points = locator::qso_points("KO24PR", wwl);
+            if duplicate {
+                qso.duplicate_qso = "D".to_string();
+                qso.qso_points = Some(0);
+            } else {
+                band.set_new_multipliers(&mut qso, dxcc);
+            }
+            band.add_qso(qso, time("180000"));
+        }
+        band.set_claimed_score();
+
+        let header = &band.header;
+        assert_eq!(header.cqsos, "2;1");
+        assert_eq!(header.cqsop, "1345");
+        assert_eq!(header.cwwls, "2;0;1");
+        assert_eq!(header.cwwlb, "0");
+        assert_eq!(header.cexcs, "0;0;1");
+        assert_eq!(header.cdxcs, "2;0;1");
+        assert_eq!(header.ctosc, "1345");
+        assert_eq!(header.codxc, "SM5BB;JO89AA;736");
+    }
+
+    #[test]
+    fn claimed_score_of_empty_band() {
+        let mut band = Band::new(Reg1testHeader::default());
+        band.set_claimed_score();
+        assert_eq!(band.header.cqsos, "0;1");
+        assert_eq!(band.header.ctosc, "0");
+        assert_eq!(band.header.codxc, "");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::error::Error;
+use crate::reg1test::{Reg1testLog, Reg1testQSORecord};
+
+#[derive(PartialEq)]
+enum Section {
+    None,
+    Header,
+    Remarks,
+    QSORecords,
+    Unknown,
+}
+
+// Reads REG1TEST (EDI) file contents: [REG1TEST;1] header lines, [Remarks] text
+// and [QSORecords;N] rows. Lines of unknown sections are ignored.
+pub fn read_edi(input: &str) -> Result<Reg1testLog, Error> {
+    let mut log = Reg1testLog::default();
+    let mut section = Section::None;
+    let mut header_found = false;
+    log.remarks.multi_line.clear();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_number = line_index + 1;
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[')
+            && trimmed.ends_with(']')
+            && (section != Section::Remarks || is_section_name(trimmed))
+        {
+            let name = &trimmed[1..trimmed.len() - 1];
+            let upper_name = name.to_ascii_uppercase();
+            section = if upper_name.starts_with("REG1TEST") {
+                log.header.name = name.to_string();
+                header_found = true;
+                Section::Header
+            } else if upper_name == "REMARKS" {
+                Section::Remarks
+            } else if upper_name.starts_with("QSORECORDS") {
+                Section::QSORecords
+            } else {
+                Section::Unknown
+            };
+            continue;
+        }
+
+        match section {
+            Section::Header => {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                match line.split_once('=') {
+                    Some((key, value)) => {
+                        log.header.set_field(key.trim(), value.trim());
+                    }
+                    None => {
+                        return Err(Error::Parse(format!(
+                            "line {}: header line without '='",
+                            line_number
+                        )));
+                    }
+                }
+            }
+            Section::Remarks => log.remarks.multi_line.push(line.trim_end().to_string()),
+            Section::QSORecords => {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                let qso = read_qso_record(trimmed)
+                    .map_err(|err| Error::Parse(format!("line {}: {}", line_number, err)))?;
+                log.records.qso_records.push(qso);
+                log.records.count += 1;
+            }
+            Section::None => {
+                if !trimmed.is_empty() {
+                    return Err(Error::Parse(format!(
+                        "line {}: [REG1TEST;1] section expected",
+                        line_number
+                    )));
+                }
+            }
+            Section::Unknown => continue,
+        }
+    }
+
+    if !header_found {
+        return Err(Error::Parse("[REG1TEST;1] section not found".to_string()));
+    }
+    if log.records.count == 0 {
+        return Err(Error::Parse("no QSO records found".to_string()));
+    }
+    Ok(log)
+}
+
+// Known section names end the remarks, other bracketed remark lines are text
+fn is_section_name(line: &str) -> bool {
+    let upper_line = line.to_ascii_uppercase();
+    upper_line.starts_with("[REG1TEST") || upper_line.starts_with("[QSORECORDS")
+}
+
+// date;time;call;mode;sent RST;sent nr;rcvd RST;rcvd nr;rcvd exchange;rcvd WWL;points;
+// new exchange;new WWL;new DXCC;duplicate
+pub fn read_qso_record(line: &str) -> Result<Reg1testQSORecord, String> {
+    let fields: Vec<&str> = line.split(';').map(str::trim).collect();
+    if fields.len() != 15 {
+        return Err(format!("QSO record has {} fields, expected 15", fields.len()));
+    }
+
+    let number = |index: usize, name: &str| -> Result<u16, String> {
+        if fields[index].is_empty() {
+            return Ok(0);
+        }
+        fields[index]
+            .parse()
+            .map_err(|_| format!("{} '{}' is not a number", name, fields[index]))
+    };
+
+    Ok(Reg1testQSORecord {
+        date: fields[0].to_string(),
+        time: fields[1].to_string(),
+        call: fields[2].to_string(),
+        mode_code: fields[3].chars().next().unwrap_or('0'),
+        sent_rst: fields[4].to_string(),
+        sent_qso_number: number(5, "sent QSO number")?,
+        received_rst: fields[6].to_string(),
+        received_qso_number: number(7, "received QSO number")?,
+        received_exchange: fields[8].to_string(),
+        received_wwl: fields[9].to_string(),
+        qso_points: match fields[10] {
+            "" => None,
+            points => Some(
+                points
+                    .parse()
+                    .map_err(|_| format!("QSO points '{}' is not a number", points))?,
+            ),
+        },
+        new_exchange: fields[11].to_string(),
+        new_wwl: fields[12].to_string(),
+        new_dxcc: fields[13].to_string(),
+        duplicate_qso: fields[14].to_string(),
+    })
+}
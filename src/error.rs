@@ -0,0 +1,46 @@
+use std::fmt;
+use std::io;
+
+// Errors of adi2edi, each category exits with its own code
+#[derive(Debug)]
+pub enum Error {
+    Usage(String),            // invalid command line arguments or file names
+    Io(String, io::Error),    // file cannot be read or written: context and cause
+    Parse(String),            // input file (or station profile) cannot be parsed
+    Conversion(String),       // ADIF field value cannot be converted to REG1TEST
+    Validation(usize),        // number of errors found by EDI validator
+}
+
+impl Error {
+    // Process exit code; 2 is also used by command line parser for usage errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::Io(_, _) => 3,
+            Error::Parse(_) => 4,
+            Error::Conversion(_) => 5,
+            Error::Validation(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(message) => write!(f, "{}", message),
+            Error::Io(context, err) => write!(f, "{}: {}", context, err),
+            Error::Parse(message) => write!(f, "{}", message),
+            Error::Conversion(message) => write!(f, "{}", message),
+            Error::Validation(errors) => write!(f, "{} validation error(s) found", errors),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod adif; // ADIF data model
+pub mod adi; // ADI reader
+pub mod adx; // ADX reader
+pub mod config; // station profile
+pub mod contests; // built-in contest calendar
+pub mod converter; // ADIF to REG1TEST and back
+pub mod edi; // REG1TEST (EDI) reader
+pub mod error; // error categories and exit codes
+pub mod locator; // Maidenhead locator and QSO distance
+pub mod reg1test; // REG1TEST data model
+pub mod sessions; // splitting of a log into operating sessions
+pub mod validator; // REG1TEST conformance checks
+
+pub use crate::converter::{BandOutput, ConvertOptions, InputFormat};
+pub use crate::error::Error;
+pub use crate::reg1test::Reg1testLog as EdiLog;
+
+use crate::adi::read_adi;
+use crate::adif::AdifFile;
+use crate::adx::read_adx;
+use crate::contests::{Session, detect_sessions};
+use crate::converter::{convert_to_reg1test, is_contest_qso, qso_label};
+use crate::sessions::{LogSession, SessionSplit, split_sessions};
+use strip_bom::*;
+
+// Reads ADI (or ADX) file contents
+pub fn read_adif(input: &str, format: InputFormat) -> Result<AdifFile, Error> {
+    match format {
+        InputFormat::Adi => read_adi(input.strip_bom())
+            .map_err(|err| Error::Parse(format!("cannot parse adi file: {}", err))),
+        InputFormat::Adx => read_adx(input.strip_bom())
+            .map_err(|err| Error::Parse(format!("cannot parse adx file: {}", err))),
+    }
+}
+
+// Converts ADI (or ADX) file contents to REG1TEST logs, one log per band, station
+// locator and callsign. Problems which do not stop the conversion, e.g. skipped QSOs
+// or missing fields, are added to 'warnings', also when an error is returned.
+pub fn convert(input: &str, options: &ConvertOptions, warnings: &mut Vec<String>) -> Result<Vec<EdiLog>, Error> {
+    convert_to_reg1test(&read_adif(input, options.input_format)?, options, warnings)
+}
+
+// Converts ADI (or ADX) file contents to EDI files, one file per band
+pub fn convert_to_edi(
+    input: &str,
+    options: &ConvertOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<BandOutput>, Error> {
+    Ok(BandOutput::from_logs(&convert(input, options, warnings)?))
+}
+
+// Converts QSOs of every contest session of the built-in calendar found in ADI (or ADX)
+// file contents to EDI files, one file per band of the contest. Contest name is used
+// as TName unless the station profile has one.
+pub fn convert_contests(
+    input: &str,
+    options: &ConvertOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<(Session, Vec<BandOutput>)>, Error> {
+    let mut adif = read_adif(input, options.input_format)?;
+    retain_dated_qsos(&mut adif, warnings);
+    // Sessions are looked for in QSOs with the CONTEST_ID and modes not excluded only
+    adif.records.retain(|record| is_contest_qso(record, options, warnings));
+    let sessions = detect_sessions(&adif);
+    if sessions.is_empty() {
+        return Err(Error::Conversion("no QSOs of a known contest found".to_string()));
+    }
+
+    let mut results = Vec::new();
+    for session in sessions {
+        let mut session_options = options.clone();
+        session_options.start = Some(session.start);
+        session_options.end = Some(session.end);
+        if session_options.profile.tname.is_none() {
+            session_options.profile.tname = Some(session.contest.name.to_string());
+        }
+        let mut logs = convert_to_reg1test(&adif, &session_options, warnings)?;
+        logs.retain(|log| session.contest.has_band(&log.header.pband));
+        if logs.is_empty() {
+            continue;
+        }
+        let band_outputs = BandOutput::from_logs(&logs);
+        results.push((session, band_outputs));
+    }
+    Ok(results)
+}
+
+// Splits QSOs of ADI (or ADX) file contents into sessions and converts every session
+// to EDI files, one file per band
+pub fn convert_sessions(
+    input: &str,
+    options: &ConvertOptions,
+    split: &SessionSplit,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<(LogSession, Vec<BandOutput>)>, Error> {
+    let mut adif = read_adif(input, options.input_format)?;
+    retain_dated_qsos(&mut adif, warnings);
+    let sessions = split_sessions(&adif, split, options, warnings);
+    if sessions.is_empty() {
+        return Err(Error::Conversion("no QSOs in any session".to_string()));
+    }
+
+    let mut results = Vec::new();
+    for session in sessions {
+        let mut session_options = options.clone();
+        session_options.start = Some(session.start);
+        session_options.end = Some(session.end);
+        let band_outputs = BandOutput::from_logs(&convert_to_reg1test(&adif, &session_options, warnings)?);
+        results.push((session, band_outputs));
+    }
+    Ok(results)
+}
+
+// Drops QSOs without valid QSO_DATE and TIME_ON, they cannot be part of a session
+fn retain_dated_qsos(adif: &mut AdifFile, warnings: &mut Vec<String>) {
+    adif.records.retain(|record| {
+        let dated = record.date_time().is_some();
+        if !dated {
+            warnings.push(format!("{}: no valid QSO_DATE and TIME_ON, QSO skipped", qso_label(record)));
+        }
+        dated
+    });
+}
@@ -0,0 +1,190 @@
+// Maidenhead locator (WWL) conversions and QSO distance as described in
+// IARU Region 1 VHF Managers Handbook
+
+use std::fmt;
+use std::str::FromStr;
+
+const EARTH_RADIUS_KM: f64 = 6371.291; // 111.2 km per degree
+
+// Character ranges and cell sizes in degrees (longitude, latitude) of each pair
+const PAIRS: [(char, char, f64, f64); 5] = [
+    ('A', 'R', 20.0, 10.0),                            // field
+    ('0', '9', 2.0, 1.0),                              // square
+    ('A', 'X', 5.0 / 60.0, 2.5 / 60.0),                // subsquare
+    ('0', '9', 0.5 / 60.0, 0.25 / 60.0),               // extended square
+    ('A', 'X', 0.5 / 60.0 / 24.0, 0.25 / 60.0 / 24.0), // extended subsquare
+];
+
+// Maidenhead locator of 4, 6, 8 or 10 characters with valid ranges, in upper case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locator(String);
+
+impl Locator {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Large square only (4 characters), scored differently by some contests
+    pub fn is_square(&self) -> bool {
+        self.0.len() == 4
+    }
+
+    // Locator for EDI files: 8 and 10 character locators are truncated to 6 characters
+    pub fn to_edi(&self) -> String {
+        self.0.chars().take(6).collect()
+    }
+}
+
+impl FromStr for Locator {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let locator = text.trim().to_ascii_uppercase();
+        if !locator.is_ascii() {
+            return Err(format!("locator '{}' should have ASCII letters and digits only", text.trim()));
+        }
+        if !matches!(locator.len(), 4 | 6 | 8 | 10) {
+            return Err(format!("locator '{}' should be 4, 6, 8 or 10 characters", text.trim()));
+        }
+        let chars: Vec<char> = locator.chars().collect();
+        for (i, (first, last, _, _)) in PAIRS.iter().enumerate().take(chars.len() / 2) {
+            if !(*first..=*last).contains(&chars[2 * i]) || !(*first..=*last).contains(&chars[2 * i + 1]) {
+                return Err(format!(
+                    "locator '{}' characters {} and {} should be {} to {}",
+                    text.trim(),
+                    2 * i + 1,
+                    2 * i + 2,
+                    first,
+                    last
+                ));
+            }
+        }
+        Ok(Locator(locator))
+    }
+}
+
+impl fmt::Display for Locator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Returns latitude and longitude in degrees of the centre of a 4, 6, 8 or 10 character locator
+pub fn locator_to_coordinates(locator: &str) -> Option<(f64, f64)> {
+    let locator: Locator = locator.parse().ok()?;
+    let chars: Vec<char> = locator.as_str().chars().collect();
+
+    let mut longitude = -180.0;
+    let mut latitude = -90.0;
+    let mut lon_size = 0.0;
+    let mut lat_size = 0.0;
+    for (i, (first, _, lon_cell, lat_cell)) in PAIRS.iter().enumerate().take(chars.len() / 2) {
+        let lon_char = chars[2 * i];
+        let lat_char = chars[2 * i + 1];
+        longitude += (lon_char as u32 - *first as u32) as f64 * lon_cell;
+        latitude += (lat_char as u32 - *first as u32) as f64 * lat_cell;
+        lon_size = *lon_cell;
+        lat_size = *lat_cell;
+    }
+
+    Some((latitude + lat_size / 2.0, longitude + lon_size / 2.0))
+}
+
+// Degrees of ADIF location "XDDD MM.MMM" (e.g. "N054 41.123"), south and west are negative
+pub fn parse_location(location: &str, is_latitude: bool) -> Option<f64> {
+    let location = location.trim();
+    let (direction, rest) = location.split_at_checked(1)?;
+    let sign = match (direction.to_ascii_uppercase().as_str(), is_latitude) {
+        ("N", true) | ("E", false) => 1.0,
+        ("S", true) | ("W", false) => -1.0,
+        _ => return None,
+    };
+    let (degrees, minutes) = rest.split_once(' ')?;
+    if degrees.len() != 3
+        || !degrees.chars().all(|c| c.is_ascii_digit())
+        || !minutes.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        return None;
+    }
+    let degrees: f64 = degrees.parse().ok()?;
+    let minutes: f64 = minutes.parse().ok()?;
+    let limit = if is_latitude { 90.0 } else { 180.0 };
+    let value = degrees + minutes / 60.0;
+    (minutes < 60.0 && value <= limit).then_some(sign * value)
+}
+
+// 6 character locator of the subsquare containing the position
+pub fn coordinates_to_locator(latitude: f64, longitude: f64) -> Option<Locator> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+    // North pole and 180 degrees east belong to the last cells
+    let lon = (longitude + 180.0).min(359.999_999);
+    let lat = (latitude + 90.0).min(179.999_999);
+    let chars = [
+        (b'A' + (lon / 20.0) as u8) as char,
+        (b'A' + (lat / 10.0) as u8) as char,
+        (b'0' + (lon % 20.0 / 2.0) as u8) as char,
+        (b'0' + (lat % 10.0) as u8) as char,
+        (b'A' + (lon % 2.0 * 12.0) as u8) as char,
+        (b'A' + (lat % 1.0 * 24.0) as u8) as char,
+    ];
+    chars.iter().collect::<String>().parse().ok()
+}
+
+// Great-circle distance in km between the centres of two locators
+pub fn distance(from: &str, to: &str) -> Option<f64> {
+    let (lat1, lon1) = locator_to_coordinates(from)?;
+    let (lat2, lon2) = locator_to_coordinates(to)?;
+    let (lat1, lon1, lat2, lon2) = (
+        lat1.to_radians(),
+        lon1.to_radians(),
+        lat2.to_radians(),
+        lon2.to_radians(),
+    );
+    let cos_angle = lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * (lon2 - lon1).cos();
+    Some(EARTH_RADIUS_KM * cos_angle.clamp(-1.0, 1.0).acos())
+}
+
+// QSO points: distance rounded down to whole km plus 1 km, so a QSO within
+// the same locator square scores the minimum of 1 point
+pub fn qso_points(from: &str, to: &str) -> Option<u32> {
+    distance(from, to).map(|km| km.trunc() as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_km(from: &str, to: &str, km: f64) {
+        let distance = distance(from, to).unwrap();
+        assert!((distance - km).abs() < 0.01, "{} - {}: {} km, expected {} km", from, to, distance, km);
+    }
+
+    #[test]
+    fn distance_between_locator_centres() {
+        assert_km("KO24PR", "KO24PR", 0.0);
+        assert_km("JO01AA", "JO02AA", 111.198); // 1 degree of latitude
+        assert_km("JO01AA", "JO01AB", 4.633); // 2.5 minutes of latitude
+        assert_km("IO91WM", "JN18EU", 345.806);
+        assert_km("KO24PR", "KP20LE", 607.292);
+        assert_km("ko24pr", "KP20LE", 607.292);
+    }
+
+    #[test]
+    fn qso_points_are_whole_km_plus_one() {
+        assert_eq!(qso_points("KO24PR", "KO24PR"), Some(1));
+        assert_eq!(qso_points("JO01AA", "JO01AB"), Some(5));
+        assert_eq!(qso_points("IO91WM", "JN18EU"), Some(346));
+        assert_eq!(qso_points("KO24PR", "KP20LE"), Some(608));
+        assert_eq!(qso_points("KO24PR", ""), None);
+    }
+
+    #[test]
+    fn invalid_locators_are_rejected() {
+        assert!("KO24P".parse::<Locator>().is_err());
+        assert!("KZ24PR".parse::<Locator>().is_err());
+        assert!("KO25é".parse::<Locator>().is_err());
+        assert_eq!("ko24pr12ab".parse::<Locator>().unwrap().to_edi(), "KO24PR");
+    }
+}
@@ -1,38 +1,23 @@
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
 mod cli; // bring cli.rs module into scope
 use crate::cli::CliArgs;
+use crate::cli::Command;
 use crate::cli::PathBuf;
 use crate::cli::clapParser;
 
-mod reg1test; // bring reg1test.rs module into scope
-mod utils; // bring utils.rs module into scope
-mod converter; // bring converter.rs module into scope
-use crate::converter::convert_to_reg1test;
+use adi2edi::config::StationProfile;
+use adi2edi::converter::convert_to_adif;
+use adi2edi::edi::read_edi;
+use adi2edi::validator::{Severity, validate_edi};
+use adi2edi::contests::detect_sessions;
+use adi2edi::sessions::SessionSplit;
+use adi2edi::{
+    BandOutput, ConvertOptions, Error, InputFormat, convert_contests, convert_sessions, convert_to_edi, read_adif,
+};
 
 //use clap::builder::OsStr;
-use pest::Parser;
 use std::process;
-use std::str::FromStr;
 use strip_bom::*;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::fs;
-use lazy_static::lazy_static;
-use regex::Regex;
-
-// Global bool variable of Atomic type shared between main() and convert_to_reg1test()
-static SKIP_REMARKS: AtomicBool = AtomicBool::new(false);
-
-lazy_static! {
-    static ref RE_PBAND: Regex = Regex::new(r"PBand=(\d+,*\d+\s\w+)").unwrap();
-}
-
-
-#[derive(Parser)]
-#[grammar = r"adi.pest"]
-pub struct AdiParser;
 
 
 // fn change_file_name(path: impl AsRef<Path>, name: &str) -> PathBuf {
@@ -45,61 +30,165 @@ pub struct AdiParser;
 //     result
 // }
 
-fn main() -> std::io::Result<()> {
+// Checks EDI file and reports violations found
+fn validate(edi_file: &PathBuf) -> Result<(), Error> {
+    let unparsed_string = fs::read_to_string(edi_file)
+        .map_err(|err| Error::Io("cannot open edi file".to_string(), err))?;
+
+    let violations = validate_edi(unparsed_string.strip_bom());
+    for violation in violations.iter() {
+        println!("{}", violation);
+    }
+    let errors = violations.iter().filter(|v| v.severity == Severity::Error).count();
+    let warnings = violations.len() - errors;
+    println!(
+        "{}: {} error(s), {} warning(s)",
+        edi_file.to_str().unwrap(),
+        errors,
+        warnings
+    );
+    if errors > 0 {
+        return Err(Error::Validation(errors));
+    }
+    Ok(())
+}
+
+// Lists contest sessions of the built-in calendar found in ADI (or ADX) file
+fn list_contests(adif_file: &PathBuf) -> Result<(), Error> {
+    let format = match adif_file.extension().and_then(|e| e.to_str()) {
+        Some("adi") => InputFormat::Adi,
+        Some("adx") => InputFormat::Adx,
+        _ => return Err(Error::Usage("input file without .adi or .adx extension".to_string())),
+    };
+    let unparsed_string = fs::read_to_string(adif_file)
+        .map_err(|err| Error::Io("cannot open input file".to_string(), err))?;
+
+    let sessions = detect_sessions(&read_adif(&unparsed_string, format)?);
+    for session in sessions.iter() {
+        println!("{}", session);
+    }
+    println!("{}: {} contest session(s)", adif_file.to_str().unwrap(), sessions.len());
+    Ok(())
+}
+
+// Writes EDI files, one per band, to terminal or to files named after 'out_file'.
+// File name gets band suffix if there are several bands or a prefix is given.
+fn write_edi(band_outputs: &[BandOutput], out_file: Option<&PathBuf>, prefix: Option<&str>) -> Result<(), Error> {
+    let Some(out_file) = out_file else {
+        let texts: Vec<&str> = band_outputs.iter().map(|band_output| band_output.text.as_str()).collect();
+        print!("{}", texts.join("\n"));
+        return Ok(());
+    };
+
+    // Get stem of EDI file
+    let stem = out_file.file_stem().unwrap().to_str().unwrap();
+    for band_output in band_outputs.iter() {
+        let filename = match prefix {
+            Some(prefix) => format!("{}_{}_{}", stem, prefix, band_output.file_suffix),
+            None if band_outputs.len() > 1 => format!("{}_{}", stem, band_output.file_suffix),
+            None => stem.to_string(),
+        };
+        let mut file = out_file.clone();
+        file.set_file_name(filename);
+        file.set_extension(out_file.extension().unwrap());
+        save(&file, &band_output.text)?;
+    }
+    Ok(())
+}
+
+// Prints warnings of the conversion
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings.iter() {
+        eprintln!("WARNING: {}", warning);
+    }
+}
+
+// Writes results to file
+fn save(file: &PathBuf, contents: &str) -> Result<(), Error> {
+    fs::write(file, contents).map_err(|err| {
+        Error::Io(format!("cannot write file {}", file.to_str().unwrap()), err)
+    })?;
+    println!("Results successfully saved to: {}", file.to_str().unwrap());
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("ERROR: {}", err);
+        if let Error::Usage(_) = err {
+            eprintln!("\nFor more information, try '--help'.");
+        }
+        process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<(), Error> {
     let args = CliArgs::parse();
-    let adi_file; //: PathBuf = Default::default();
-    let mut edi_file: PathBuf = Default::default();
+
+    match args.command {
+        Some(Command::Validate { file }) => return validate(&file),
+        Some(Command::Contests { file }) => return list_contests(&file),
+        None => (),
+    }
+
+    let in_file; //: PathBuf = Default::default();
+    let in_format; // "adi", "adx" or "edi"
+    let out_format; // "edi" or "adi" (from EDI input)
+    let mut out_file: PathBuf = Default::default();
     let save_to_file;
 
-    // Validate name of ADI file
+    // Validate name of ADI (ADX or EDI) file
     if let Some(s) = args.infile {
         if !s.is_file() {
-            eprintln!("ERROR: input file not found");
-            process::exit(0);
+            return Err(Error::Usage("input file not found".to_string()));
         }
         if let Some(e) = s.extension() {
-            if e != "adi" {
-                eprintln!("ERROR: input file extension is incorrect");
-                process::exit(0);    
-            }
+            in_format = match e.to_str() {
+                Some("adi") => "adi",
+                Some("adx") => "adx",
+                Some("edi") => "edi",
+                _ => return Err(Error::Usage("input file extension is incorrect".to_string())),
+            };
         } else {
-            eprintln!("ERROR: input file without .adi extension");
-            process::exit(0);
+            return Err(Error::Usage("input file without .adi, .adx or .edi extension".to_string()));
         }
+        out_format = if in_format == "edi" { "adi" } else { "edi" };
 
         // Input file name is correct
-        adi_file = s.clone();
+        in_file = s.clone();
     } else {
-        eprintln!("ERROR: input file not specified");
-        eprintln!("\nUsage: adi2edi.exe [OPTIONS]");
-        eprintln!("\nFor more information, try '--help'.");
-    process::exit(0);
+        return Err(Error::Usage("input file not specified".to_string()));
     }
     
-    // Validate name of EDI file
+    // Validate name of EDI (or ADI) file
     if let Some(s) = args.outfile
     {
         if let Some(e) = s.extension() {
-            if e != "edi" {
-                eprintln!("ERROR: output file extension is incorrect");
-                process::exit(0);
+            if e != out_format {
+                return Err(Error::Usage("output file extension is incorrect".to_string()));
             }
         } else {
-            eprintln!("ERROR: output file without .edi extension");
-            process::exit(0);
+            return Err(Error::Usage(format!("output file without .{} extension", out_format)));
         }
 
         // Output file name is correct       
-       edi_file = s.clone();
+       out_file = s.clone();
        // Save results to file
        save_to_file = true;
     }
     else // output file not specified
     {
         if args.to_file {
-            // Create output file from input file with extension .edi
-            edi_file.clone_from(&adi_file);
-            edi_file.set_extension("edi");
+            // Create output file from input file with extension .edi (or .adi)
+            out_file.clone_from(&in_file);
+            out_file.set_extension(out_format);
+            // Do not overwrite an existing ADIF log with the converted one
+            if in_format == "edi" && out_file.exists() {
+                return Err(Error::Usage(format!(
+                    "file {} already exists, specify output file",
+                    out_file.to_str().unwrap()
+                )));
+            }
             // Save results to file
             save_to_file = true;
         }
@@ -110,60 +199,99 @@ fn main() -> std::io::Result<()> {
         
     }
     
-    // println!("Input filename: {:?}", adi_file.to_str().unwrap());
-    // println!("Output filename: {:?}", edi_file.to_str().unwrap());
+    // println!("Input filename: {:?}", in_file.to_str().unwrap());
+    // println!("Output filename: {:?}", out_file.to_str().unwrap());
     // println!("Include remarks: {}", !args.skip_remarks);
 
-    SKIP_REMARKS.store(args.skip_remarks, Ordering::Relaxed);
+    // Load station profile if specified
+    let profile = match args.config {
+        Some(config_file) => StationProfile::from_file(&config_file)?,
+        None => StationProfile::default(),
+    };
 
-    let unparsed_string = fs::read_to_string(adi_file.to_str().unwrap()).unwrap_or_else(|err| {
-        eprintln!("ERROR: cannot open adi file: {}", err);
-        process::exit(0);
-    });
+    let unparsed_string = fs::read_to_string(&in_file)
+        .map_err(|err| Error::Io("cannot open input file".to_string(), err))?;
 
-    // Run ADI parser and if successful collect in 'parse_result'
-    match AdiParser::parse(Rule::adi, unparsed_string.strip_bom()) {
-        Err(parse_error) => {
-            eprintln!("ERROR: cannot parse adi file: {}", parse_error);
-            process::exit(0);
+    // Run EDI reader and EDI to ADI converter
+    if in_format == "edi" {
+        let reg1test_log = read_edi(unparsed_string.strip_bom())
+            .map_err(|err| Error::Parse(format!("cannot parse edi file: {}", err)))?;
+        let mut warnings = Vec::new();
+        let adif_output = convert_to_adif(&reg1test_log, &mut warnings).to_string();
+        print_warnings(&warnings);
+        if save_to_file {
+            save(&out_file, &adif_output)?;
+        } else {
+            print!("{}", adif_output);
         }
-        Ok(parse_result) => {
-            // Run ADI to Reg1test (EDI) converter and save results in string 'reg1test_output'
-            let reg1test_output = convert_to_reg1test(parse_result).unwrap();
-            if save_to_file {
-                // Split results into band sections
-                let band_section: Vec<&str> = reg1test_output.trim().split("\n\n").collect();
-                if band_section.len() > 1 {
-                    // Get stem of EDI file
-                    let stem = edi_file.file_stem().unwrap();
-                    band_section.iter().for_each(|r| {
-                        // Parse PBand value from section string using Regex expression
-                        match RE_PBAND.captures(&r) {
-                            Some(cap) => {
-                                // Replace comma with underscore and remove spaces
-                                let suffix = cap.get(1).unwrap().as_str().replace(",", "_").replace(" ", "");
-                                // Create new file name from the stem of EDI file stem and append suffix string
-                                let filename = String::from_str(stem.to_str().unwrap()).unwrap() + "_" + suffix.as_str();
-                                let mut file = edi_file.clone();
-                                file.set_file_name(filename);
-                                file.set_extension(edi_file.extension().unwrap());
-                                fs::write(file.to_str().unwrap(), r).unwrap();
-                                println!("Results successfully saved to: {}", file.to_str().unwrap());
-                            },
-                            None => {
-                                eprintln!("ERROR: parsing file suffix from 'PBand' failed.");
-                                process::exit(0);    
-                            }
-                        }
-                    }); // for_each
-                } else {
-                    fs::write(edi_file.to_str().unwrap(), reg1test_output)?;
-                    println!("Results successfully saved to: {}", edi_file.to_str().unwrap());
-                }
+        return Ok(());
+    }
+
+    let options = ConvertOptions {
+        input_format: if in_format == "adx" { InputFormat::Adx } else { InputFormat::Adi },
+        skip_remarks: args.skip_remarks,
+        dupe_by_mode: args.dupe_by_mode,
+        dupe_window: args.dupe_window,
+        profile,
+        start: args.start,
+        end: args.end,
+        contest_id: args.contest_id,
+        renumber: args.renumber,
+    };
+    if let (Some(start), Some(end)) = (options.start, options.end)
+        && start >= end
+    {
+        return Err(Error::Usage("contest end should be after its start".to_string()));
+    }
+
+    let out_file = save_to_file.then_some(&out_file);
+    let mut warnings = Vec::new();
+
+    // Convert QSOs of every contest session found, file names get contest and date prefix
+    if args.detect_contests {
+        let results = convert_contests(&unparsed_string, &options, &mut warnings);
+        print_warnings(&warnings);
+        for (index, (session, band_outputs)) in results?.iter().enumerate() {
+            if out_file.is_some() {
+                println!("{}", session);
+            } else if index > 0 {
+                println!();
+            }
+            write_edi(band_outputs, out_file, Some(&session.file_prefix()))?;
+        }
+        return Ok(());
+    }
+
+    // Convert QSOs of every session, file names get session date prefix
+    let split = match args.split_gap {
+        Some(gap) => Some(SessionSplit::Gap(gap)),
+        None if !args.session.is_empty() => Some(SessionSplit::Windows(args.session)),
+        None => None,
+    };
+    if let Some(split) = split {
+        let results = convert_sessions(&unparsed_string, &options, &split, &mut warnings);
+        print_warnings(&warnings);
+        let results = results?;
+        for (index, (session, band_outputs)) in results.iter().enumerate() {
+            // Start time is added if there are several sessions on the same date
+            let date = session.start.date();
+            let prefix = if results.iter().filter(|(s, _)| s.start.date() == date).count() > 1 {
+                session.start.format("%Y%m%d_%H%M").to_string()
             } else {
-                println!("{}", reg1test_output);
+                session.start.format("%Y%m%d").to_string()
+            };
+            if out_file.is_some() {
+                println!("{}", session);
+            } else if index > 0 {
+                println!();
             }
+            write_edi(band_outputs, out_file, Some(&prefix))?;
         }
+        return Ok(());
     }
-    Ok(())
+
+    // Run ADI (or ADX) reader and ADI to Reg1test (EDI) converter, one output per band
+    let band_outputs = convert_to_edi(&unparsed_string, &options, &mut warnings);
+    print_warnings(&warnings);
+    write_edi(&band_outputs?, out_file, None)
 }
@@ -1,6 +1,7 @@
+use chrono::NaiveDateTime;
 use std::fmt;
 
-const DEFAULT_REMARKS: &'static str = "\
+const DEFAULT_REMARKS: &str = "\
     Converted from ADIF using 'adi2edi' converter\n\
     (https://github.com/ZilvinasJonaitis/adi2edi)\n\
     Original ADIF header below the line:\n\
@@ -9,109 +10,279 @@ const DEFAULT_REMARKS: &'static str = "\
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
-pub struct Reg1testHeader<'a> {
-    pub name: &'a str,
-    pub tdate: &'a str,
-    pub pcall: &'a str,
-    pub pwwlo: &'a str,
+pub struct Reg1testHeader {
+    pub name: String,
+    pub tdate: String,
+    pub pcall: String,
+    pub pwwlo: String,
     pub pband: String,
     pub psect: String,
     pub pclub: String,
+    pub tname: String,  // contest name
+    pub pexch: String,  // sent exchange
+    pub padr1: String,  // address of QTH, line 1
+    pub padr2: String,  // address of QTH, line 2
+    pub rname: String,  // name of responsible operator
+    pub rcall: String,  // callsign of responsible operator
+    pub radr1: String,  // address of responsible operator, line 1
+    pub radr2: String,  // address of responsible operator, line 2
+    pub rpoco: String,  // postal code
+    pub rcity: String,  // city
+    pub rcoun: String,  // country
+    pub rphon: String,  // phone number
+    pub rhbbs: String,  // home BBS or e-mail
+    pub mope1: String,  // operators, line 1
+    pub mope2: String,  // operators, line 2
+    pub stxeq: String,  // TX equipment
+    pub spowe: String,  // TX power in watts
+    pub srxeq: String,  // RX equipment
+    pub sante: String,  // antenna
+    pub santh: String,  // antenna height above ground;above sea level
+    pub cqsos: String, // claimed number of valid QSOs;band multiplier
+    pub cqsop: String, // claimed QSO points
+    pub cwwls: String, // claimed number of WWLs;bonus per new WWL;WWL multiplier
+    pub cwwlb: String, // claimed WWL bonus points
+    pub cexcs: String, // claimed number of exchanges;bonus per new exchange;exchange multiplier
+    pub cexcb: String, // claimed exchange bonus points
+    pub cdxcs: String, // claimed number of DXCCs;bonus per new DXCC;DXCC multiplier
+    pub cdxcb: String, // claimed DXCC bonus points
+    pub ctosc: String, // claimed total score
+    pub codxc: String, // claimed ODX: call;WWL;distance
 }
 
-impl Default for Reg1testHeader<'_> {
+impl Default for Reg1testHeader {
     fn default() -> Self {
         Reg1testHeader {
-            name: "REG1TEST;1",
-            tdate: "",
-            pcall: "",
-            pwwlo: "",
+            name: "REG1TEST;1".to_string(),
+            tdate: "".to_string(),
+            pcall: "".to_string(),
+            pwwlo: "".to_string(),
             pband: "".to_string(),
             psect: "".to_string(),
             pclub: "".to_string(),
+            tname: "".to_string(),
+            pexch: "".to_string(),
+            padr1: "".to_string(),
+            padr2: "".to_string(),
+            rname: "".to_string(),
+            rcall: "".to_string(),
+            radr1: "".to_string(),
+            radr2: "".to_string(),
+            rpoco: "".to_string(),
+            rcity: "".to_string(),
+            rcoun: "".to_string(),
+            rphon: "".to_string(),
+            rhbbs: "".to_string(),
+            mope1: "".to_string(),
+            mope2: "".to_string(),
+            stxeq: "".to_string(),
+            spowe: "".to_string(),
+            srxeq: "".to_string(),
+            sante: "".to_string(),
+            santh: "".to_string(),
+            cqsos: "".to_string(),
+            cqsop: "".to_string(),
+            cwwls: "".to_string(),
+            cwwlb: "".to_string(),
+            cexcs: "".to_string(),
+            cexcb: "".to_string(),
+            cdxcs: "".to_string(),
+            cdxcb: "".to_string(),
+            ctosc: "".to_string(),
+            codxc: "".to_string(),
         }
     }
 }
 
-impl fmt::Display for Reg1testHeader<'_> {
+impl fmt::Display for Reg1testHeader {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{}]\n", self.name)?;
-        write!(f, "TDate={}\n", self.tdate)?;
-        write!(f, "PCall={}\n", self.pcall.to_ascii_uppercase())?;
-        write!(f, "PWWLo={}\n", self.pwwlo.to_ascii_uppercase())?;
-        write!(f, "PBand={}", self.pband)?;
-        if self.psect != "" {
-            write!(f, "\nPSect={}", self.psect)?;
-        }
-        if self.pclub != "" {
-            write!(f, "\nPClub={}", self.pclub)?;
+        let pcall = self.pcall.to_ascii_uppercase();
+        let pwwlo = self.pwwlo.to_ascii_uppercase();
+
+        // Header lines in the order of REG1TEST specification, optional lines are
+        // written only if they have a value
+        let lines: [(&str, &str, bool); 36] = [
+            ("TName", &self.tname, false),
+            ("TDate", &self.tdate, true),
+            ("PCall", &pcall, true),
+            ("PWWLo", &pwwlo, true),
+            ("PExch", &self.pexch, false),
+            ("PAdr1", &self.padr1, false),
+            ("PAdr2", &self.padr2, false),
+            ("PSect", &self.psect, false),
+            ("PBand", &self.pband, true),
+            ("PClub", &self.pclub, false),
+            ("RName", &self.rname, false),
+            ("RCall", &self.rcall, false),
+            ("RAdr1", &self.radr1, false),
+            ("RAdr2", &self.radr2, false),
+            ("RPoCo", &self.rpoco, false),
+            ("RCity", &self.rcity, false),
+            ("RCoun", &self.rcoun, false),
+            ("RPhon", &self.rphon, false),
+            ("RHBBS", &self.rhbbs, false),
+            ("MOpe1", &self.mope1, false),
+            ("MOpe2", &self.mope2, false),
+            ("STXEq", &self.stxeq, false),
+            ("SPowe", &self.spowe, false),
+            ("SRXEq", &self.srxeq, false),
+            ("SAnte", &self.sante, false),
+            ("SAntH", &self.santh, false),
+            ("CQSOs", &self.cqsos, false),
+            ("CQSOP", &self.cqsop, false),
+            ("CWWLs", &self.cwwls, false),
+            ("CWWLB", &self.cwwlb, false),
+            ("CExcs", &self.cexcs, false),
+            ("CExcB", &self.cexcb, false),
+            ("CDXCs", &self.cdxcs, false),
+            ("CDXCB", &self.cdxcb, false),
+            ("CToSc", &self.ctosc, false),
+            ("CODXC", &self.codxc, false),
+        ];
+
+        write!(f, "[{}]", self.name)?;
+        for (key, value, required) in lines {
+            if required || !value.is_empty() {
+                write!(f, "\n{}={}", key, value)?;
+            }
         }
         Ok(())
     }
 }
 
-impl Reg1testHeader<'_> {
+// ADIF band name, REG1TEST PBand and ADIF band edges in MHz
+const BANDS: [(&str, &str, f64, f64); 15] = [
+    ("6m", "50 MHz", 50.0, 54.0),
+    ("4m", "70 MHz", 70.0, 71.0),
+    ("2m", "144 MHz", 144.0, 148.0),
+    ("70cm", "432 MHz", 420.0, 450.0),
+    ("23cm", "1,3 GHz", 1240.0, 1300.0),
+    ("13cm", "2,3 GHz", 2300.0, 2450.0),
+    ("9cm", "3,4 GHz", 3300.0, 3500.0),
+    ("6cm", "5,7 GHz", 5650.0, 5925.0),
+    ("3cm", "10 GHz", 10000.0, 10500.0),
+    ("1.25cm", "24 GHz", 24000.0, 24250.0),
+    ("6mm", "47 GHz", 47000.0, 47200.0),
+    ("4mm", "76 GHz", 75500.0, 81000.0),
+    ("2.5mm", "122 GHz", 119980.0, 123000.0),
+    ("2mm", "134 GHz", 134000.0, 149000.0),
+    ("1mm", "241 GHz", 241000.0, 250000.0),
+];
+
+impl Reg1testHeader {
+    // Sets header field by its REG1TEST name, returns false for unknown names
+    pub fn set_field(&mut self, key: &str, value: &str) -> bool {
+        let field = match key.to_ascii_uppercase().as_str() {
+            "TDATE" => &mut self.tdate,
+            "PCALL" => &mut self.pcall,
+            "PWWLO" => &mut self.pwwlo,
+            "TNAME" => &mut self.tname,
+            "PEXCH" => &mut self.pexch,
+            "PADR1" => &mut self.padr1,
+            "PADR2" => &mut self.padr2,
+            "PSECT" => &mut self.psect,
+            "PBAND" => &mut self.pband,
+            "PCLUB" => &mut self.pclub,
+            "RNAME" => &mut self.rname,
+            "RCALL" => &mut self.rcall,
+            "RADR1" => &mut self.radr1,
+            "RADR2" => &mut self.radr2,
+            "RPOCO" => &mut self.rpoco,
+            "RCITY" => &mut self.rcity,
+            "RCOUN" => &mut self.rcoun,
+            "RPHON" => &mut self.rphon,
+            "RHBBS" => &mut self.rhbbs,
+            "MOPE1" => &mut self.mope1,
+            "MOPE2" => &mut self.mope2,
+            "STXEQ" => &mut self.stxeq,
+            "SPOWE" => &mut self.spowe,
+            "SRXEQ" => &mut self.srxeq,
+            "SANTE" => &mut self.sante,
+            "SANTH" => &mut self.santh,
+            "CQSOS" => &mut self.cqsos,
+            "CQSOP" => &mut self.cqsop,
+            "CWWLS" => &mut self.cwwls,
+            "CWWLB" => &mut self.cwwlb,
+            "CEXCS" => &mut self.cexcs,
+            "CEXCB" => &mut self.cexcb,
+            "CDXCS" => &mut self.cdxcs,
+            "CDXCB" => &mut self.cdxcb,
+            "CTOSC" => &mut self.ctosc,
+            "CODXC" => &mut self.codxc,
+            _ => return false,
+        };
+        *field = value.to_string();
+        true
+    }
+
     pub fn get_band(band: &str) -> String {
-        match band.trim() {
-            "6m" => return "50 MHz".to_string(),
-            "4m" => return "70 MHz".to_string(),
-            "2m" => return "144 MHz".to_string(),
-            "70cm" => return "432 MHz".to_string(),
-            "23cm" => return "1,3 GHz".to_string(),
-            "13cm" => return "2,3 GHz".to_string(),
-            "9cm" => return "3,4 GHz".to_string(),
-            "6cm" => return "5,7 GHz".to_string(),
-            "3cm" => return "10 GHz".to_string(),
-            "1.25cm" => return "24 GHz".to_string(),
-            "6mm" => return "47 GHz".to_string(),
-            "4mm" => return "76 GHz".to_string(),
-            "2.5mm" => return "120 GHz".to_string(),
-            "2mm" => return "144 GHz".to_string(),
-            "1mm" => return "248 GHz".to_string(),
-            _ => "".to_string(),
+        let band = band.trim();
+        BANDS
+            .iter()
+            .find(|(adif_band, _, _, _)| adif_band.eq_ignore_ascii_case(band))
+            .map_or("".to_string(), |(_, pband, _, _)| pband.to_string())
+    }
+
+    // PBand is one of the REG1TEST bands, e.g. "144 MHz"
+    pub fn is_known_band(pband: &str) -> bool {
+        BANDS.iter().any(|(_, known_pband, _, _)| *known_pband == pband)
+    }
+
+    // ADIF band of a PBand value, also accepts values like "145 MHz" or "1296 MHz"
+    pub fn get_adif_band(pband: &str) -> String {
+        let pband = pband.trim();
+        if let Some((adif_band, _, _, _)) = BANDS
+            .iter()
+            .find(|(_, known_pband, _, _)| known_pband.eq_ignore_ascii_case(pband))
+        {
+            return adif_band.to_string();
         }
+
+        let (number, unit) = pband.split_at(pband.find(|c: char| c.is_alphabetic()).unwrap_or(pband.len()));
+        let f: f64 = match number.trim().replace(',', ".").parse() {
+            Ok(f) => f,
+            Err(_) => return "".to_string(),
+        };
+        let f = match unit.trim().to_ascii_uppercase().as_str() {
+            "GHZ" => f * 1000.0,
+            "MHZ" | "" => f,
+            _ => return "".to_string(),
+        };
+        BANDS
+            .iter()
+            .find(|(_, _, lower_edge, upper_edge)| f >= *lower_edge && f <= *upper_edge)
+            .map_or("".to_string(), |(adif_band, _, _, _)| adif_band.to_string())
     }
-    #[allow(dead_code)]
+
+    // Frequency in MHz, as in ADIF FREQ field
     pub fn get_band_from_freq(f: f64) -> String {
-        if f > 50.0 && f < 53.0 {return "50 MHz".to_string();}
-        if f > 70.0 && f < 71.0 {return "70 MHz".to_string();}
-        if f > 144.0 && f < 148.0 {return "144 MHz".to_string();}
-        if f > 420.0 && f < 450.0 {return "432 MHz".to_string();}
-        if f > 1240.0 && f < 1300.0 {return "1,3 GHz".to_string();}
-        if f > 2300.0 && f < 2450.0 {return "2,3 GHz".to_string();}
-        if f > 3300.0 && f < 3500.0 {return "3,4 GHz".to_string();}
-        if f > 5650.0 && f < 5925.0 {return "5,7 GHz".to_string();}
-        if f > 10000.0 && f < 10500.0 {return "10 GHz".to_string();}
-        if f > 24000.0 && f < 24250.0 {return "24 GHz".to_string();}
-        if f > 47000.0 && f < 47200.0 {return "47 GHz".to_string();}
-        if f > 75500.0 && f < 81000.0 {return "76 GHz".to_string();}
-        if f > 119980.0 && f < 12300.0 {return "120 GHz".to_string();}
-        if f > 134000.0 && f < 14900.0 {return "144 GHz".to_string();}
-        if f > 241000.0 && f < 25000.0 {return "248 GHz".to_string();}
-        return "".to_string();
+        BANDS
+            .iter()
+            .find(|(_, _, lower_edge, upper_edge)| f >= *lower_edge && f <= *upper_edge)
+            .map_or("".to_string(), |(_, pband, _, _)| pband.to_string())
     }
 }
 
 #[allow(dead_code)]
-// #[derive(Debug)]
-pub struct Reg1testRemarks<'a> {
-    pub name: &'a str,
+#[derive(Debug, Clone)]
+pub struct Reg1testRemarks {
+    pub name: String,
     pub multi_line: Vec<String>,
 }
 
-impl Default for Reg1testRemarks<'_> {
+impl Default for Reg1testRemarks {
     fn default() -> Self {
         Reg1testRemarks {
-            name: "Remarks",
+            name: "Remarks".to_string(),
             multi_line: vec![DEFAULT_REMARKS.to_owned()],
         }
     }
 }
 
-impl fmt::Display for Reg1testRemarks<'_> {
+impl fmt::Display for Reg1testRemarks {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[{}]", self.name)?;
-        if self.multi_line.len() != 0 {
+        if !self.multi_line.is_empty() {
             // If there are remark lines
             for text_line in self.multi_line.iter() {
                 write!(f, "\n{}", text_line)?;
@@ -122,26 +293,26 @@ impl fmt::Display for Reg1testRemarks<'_> {
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
-pub struct Reg1testQSOs<'a> {
-    pub name: &'a str,
+#[derive(Debug, Clone)]
+pub struct Reg1testQSOs {
+    pub name: String,
     pub count: u32,
-    pub qso_records: Vec<Reg1testQSORecord<'a>>,
+    pub qso_records: Vec<Reg1testQSORecord>,
 }
 
-impl Default for Reg1testQSOs<'_> {
+impl Default for Reg1testQSOs {
     fn default() -> Self {
         Reg1testQSOs {
-            name: "QSORecords",
+            name: "QSORecords".to_string(),
             count: 0,
             qso_records: Vec::new(),
         }
     }
 }
 
-impl fmt::Display for Reg1testQSOs<'_> {
+impl fmt::Display for Reg1testQSOs {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{};{}]\n", self.name, self.count)?;
+        writeln!(f, "[{};{}]", self.name, self.count)?;
         for qso in self.qso_records.iter() {
             write!(f, "{}", qso)?;
         }
@@ -150,51 +321,52 @@ impl fmt::Display for Reg1testQSOs<'_> {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
-pub struct Reg1testQSORecord<'a> {
-    pub date: &'a str,                // YYMMDD, 6 characters, 6
-    pub time: &'a str,                // UTC, 4 characters, with leading zeros, 4
-    pub call: &'a str,                // 3 to 14 characters, 14
-    pub mode_code: char,              // 0 or 1 character, 1
-    pub sent_rst: &'a str,            // 0 or 2 or 3 characters, 3
-    pub sent_qso_number: u16,         // 0 or 3 or 4 characters, with leading zeros, 4
-    pub received_rst: &'a str,        // 0 or 2 or 3 characters, 3
-    pub received_qso_number: u16,     // 0 or 3 or 4 characters, with leading zeros, 4
-    pub received_exchange: &'a str,   // 0 or 1 to 6 characters (see also PExch), 6
-    pub received_wwl: &'a str,        // 0 or 4 or 6 characters, World Wide Locator, 6
-    pub qso_points: &'a str,          // 1 to 6 characters, including bandmultiplier, 6
-    pub new_exchange: &'a str,        // 0 or 1 character, "N" if QSO is a new exchange, 1
-    pub new_wwl: &'a str,             // 0 or 1 character, "N" if QSO is a new WWL, 1
-    pub new_dxcc: &'a str,            // 0 or 1 character, "N" if QSO is a new DXCCL, 1
-    pub duplicate_qso: &'a str,       // 0 or 1 character, "D" if contact is a duplicate QSO, 1
+#[derive(Debug, Clone)]
+pub struct Reg1testQSORecord {
+    pub date: String,                     // YYMMDD, 6 characters, 6
+    pub time: String,                     // UTC, 4 characters, with leading zeros, 4
+    pub call: String,                     // 3 to 14 characters, 14
+    pub mode_code: char,                  // 0 or 1 character, 1
+    pub sent_rst: String,                 // 0 or 2 or 3 characters, 3
+    pub sent_qso_number: u16,             // 0 or 3 or 4 characters, with leading zeros, 4
+    pub received_rst: String,             // 0 or 2 or 3 characters, 3
+    pub received_qso_number: u16,         // 0 or 3 or 4 characters, with leading zeros, 4
+    pub received_exchange: String,        // 0 or 1 to 6 characters (see also PExch), 6
+    pub received_wwl: String,             // 0 or 4 or 6 characters, World Wide Locator, 6
+    pub qso_points: Option<u32>,          // 1 to 6 characters, including bandmultiplier, 6
+    pub new_exchange: String,             // 0 or 1 character, "N" if QSO is a new exchange, 1
+    pub new_wwl: String,                  // 0 or 1 character, "N" if QSO is a new WWL, 1
+    pub new_dxcc: String,                 // 0 or 1 character, "N" if QSO is a new DXCCL, 1
+    pub duplicate_qso: String,            // 0 or 1 character, "D" if contact is a duplicate QSO, 1
 }
 
-impl Default for Reg1testQSORecord<'_> {
+impl Default for Reg1testQSORecord {
     fn default() -> Self {
         Reg1testQSORecord {
-            date: "",
-            time: "",
-            call: "",
+            date: "".to_string(),
+            time: "".to_string(),
+            call: "".to_string(),
             mode_code: '0',
-            sent_rst: "",
+            sent_rst: "".to_string(),
             sent_qso_number: 0,
-            received_rst: "",
+            received_rst: "".to_string(),
             received_qso_number: 0,
-            received_exchange: "",
-            received_wwl: "",
-            qso_points: "",
-            new_exchange: "",
-            new_wwl: "",
-            new_dxcc: "",
-            duplicate_qso: "",
+            received_exchange: "".to_string(),
+            received_wwl: "".to_string(),
+            qso_points: None,
+            new_exchange: "".to_string(),
+            new_wwl: "".to_string(),
+            new_dxcc: "".to_string(),
+            duplicate_qso: "".to_string(),
         }
     }
 }
 
-impl fmt::Display for Reg1testQSORecord<'_> {
+impl fmt::Display for Reg1testQSORecord {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut sent_qso_num = String::from(""); 
         let mut received_qso_num = String::from("");
+        let mut qso_points = String::from("");
 
         if self.sent_qso_number > 0 {
             if self.sent_qso_number > 999 {
@@ -213,9 +385,13 @@ impl fmt::Display for Reg1testQSORecord<'_> {
             }
         }
 
-        write!(
+        if let Some(points) = self.qso_points {
+            qso_points = points.to_string();
+        }
+
+        writeln!(
             f,
-            "{};{};{};{};{};{};{};{};{};{};{};{};{};{};{}\n",
+            "{};{};{};{};{};{};{};{};{};{};{};{};{};{};{}",
             self.date,
             self.time,
             self.call.to_ascii_uppercase(),
@@ -226,7 +402,7 @@ impl fmt::Display for Reg1testQSORecord<'_> {
             received_qso_num,
             self.received_exchange,
             self.received_wwl.to_ascii_uppercase(), // IARU-R1 VHF Handbook 10.02: 5.2.5 Notation of locators  
-            self.qso_points,
+            qso_points,
             self.new_exchange.to_ascii_uppercase(),
             self.new_wwl.to_ascii_uppercase(),
             self.new_dxcc.to_ascii_uppercase(),
@@ -235,33 +411,101 @@ impl fmt::Display for Reg1testQSORecord<'_> {
     }
 }
 
-impl Reg1testQSORecord<'_> {
-    pub fn get_mode(mode_string: &str) -> char {
-        match mode_string.trim() {
-            "SSB" => {
-                return '1';
-            }
-            "CW" => {
-                return '2';
-            }
-            "AM" => {
-                return '5';
-            }
-            "FM" => {
-                return '6';
-            }
-            "RTTY" => {
-                return '7';
-            }
-            "SSTV" => {
-                return '8';
-            }
-            "ATV" => {
-                return '9';
-            }
-            _ => {
-                return '0';
-            }
+impl Reg1testQSORecord{
+    // QSO date and time, None if either is missing or malformed
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&format!("{}{}", self.date, self.time), "%y%m%d%H%M").ok()
+    }
+
+    // ADIF mode of a REG1TEST mode code, mixed modes give the sent mode
+    pub fn get_adif_mode(mode_code: char) -> &'static str {
+        match mode_code {
+            '1' | '3' => "SSB",
+            '2' | '4' => "CW",
+            '5' => "AM",
+            '6' => "FM",
+            '7' => "RTTY",
+            '8' => "SSTV",
+            '9' => "ATV",
+            _ => "",
+        }
+    }
+
+    // REG1TEST mode code of ADIF MODE and SUBMODE, case-insensitive. SSB and CW QSOs
+    // with a 2 character (phone) RST one way and 3 character (CW) RST the other way
+    // are mixed modes: 3 for SSB sent and CW received, 4 for CW sent and SSB received.
+    // Digital modes (FT8, FT4, JT65, Q65, MSK144, PSK, ...) are 0, none of the modes.
+    pub fn get_mode(mode: &str, submode: &str, sent_rst: &str, received_rst: &str) -> char {
+        let mode_code = match mode.trim().to_ascii_uppercase().as_str() {
+            "SSB" | "USB" | "LSB" => '1',
+            "CW" | "PCW" => '2',
+            "AM" => '5',
+            "FM" => '6',
+            "RTTY" | "ASCI" => '7',
+            "SSTV" => '8',
+            "ATV" => '9',
+            // Submodes exported as MODE by some loggers, or MODE missing
+            _ => match submode.trim().to_ascii_uppercase().as_str() {
+                "USB" | "LSB" => '1',
+                "PCW" => '2',
+                "ASCI" => '7',
+                _ => '0',
+            },
+        };
+
+        let is_phone_rst = |rst: &str| rst.trim().len() == 2;
+        let is_cw_rst = |rst: &str| rst.trim().len() == 3;
+        match mode_code {
+            '1' | '2' if is_phone_rst(sent_rst) && is_cw_rst(received_rst) => '3',
+            '1' | '2' if is_cw_rst(sent_rst) && is_phone_rst(received_rst) => '4',
+            _ => mode_code,
         }
     }
 }
+
+// Complete REG1TEST file of one band
+#[derive(Debug, Clone, Default)]
+pub struct Reg1testLog {
+    pub header: Reg1testHeader,
+    pub remarks: Reg1testRemarks,
+    pub records: Reg1testQSOs,
+}
+
+impl fmt::Display for Reg1testLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n{}\n{}", self.header, self.remarks, self.records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_codes_of_adif_modes() {
+        assert_eq!(Reg1testQSORecord::get_mode("SSB", "USB", "", ""), '1');
+        assert_eq!(Reg1testQSORecord::get_mode("cw", "", "", ""), '2');
+        assert_eq!(Reg1testQSORecord::get_mode("FM", "", "59", "59"), '6');
+        assert_eq!(Reg1testQSORecord::get_mode("", "LSB", "", ""), '1');
+        assert_eq!(Reg1testQSORecord::get_mode("FT8", "", "-10", "-05"), '0');
+        assert_eq!(Reg1testQSORecord::get_mode("MFSK", "FT4", "", ""), '0');
+    }
+
+    #[test]
+    fn phone_and_cw_reports_give_mixed_mode() {
+        // SSB sent (2 character RST), CW received (3 character RST)
+        assert_eq!(Reg1testQSORecord::get_mode("SSB", "", "59", "599"), '3');
+        assert_eq!(Reg1testQSORecord::get_mode("CW", "", "59", "599"), '3');
+        // CW sent, SSB received
+        assert_eq!(Reg1testQSORecord::get_mode("CW", "", "599", "59"), '4');
+        assert_eq!(Reg1testQSORecord::get_mode("SSB", "", "599", "59"), '4');
+    }
+
+    #[test]
+    fn same_kind_or_missing_reports_keep_mode() {
+        assert_eq!(Reg1testQSORecord::get_mode("SSB", "", "59", "59"), '1');
+        assert_eq!(Reg1testQSORecord::get_mode("CW", "", "599", "599"), '2');
+        assert_eq!(Reg1testQSORecord::get_mode("CW", "", "", "59"), '2');
+        assert_eq!(Reg1testQSORecord::get_mode("FM", "", "59", "599"), '6');
+    }
+}
@@ -0,0 +1,80 @@
+use crate::adif::AdifFile;
+use crate::converter::{ConvertOptions, is_contest_qso};
+use chrono::{Duration, NaiveDateTime};
+use std::fmt;
+
+// Splitting of a log covering several operating sessions, e.g. activity evenings
+
+// How QSOs are split into sessions
+#[derive(Debug, Clone)]
+pub enum SessionSplit {
+    Gap(u32),                                    // new session after a pause of more than given minutes
+    Windows(Vec<(NaiveDateTime, NaiveDateTime)>), // start and end (UTC) of every session
+}
+
+// QSOs made between start and end, converted to EDI files of their own
+#[derive(Debug, Clone)]
+pub struct LogSession {
+    pub start: NaiveDateTime, // UTC
+    pub end: NaiveDateTime,   // UTC, QSOs from this time on are not part of the session
+    pub qsos: usize,
+}
+
+impl fmt::Display for LogSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Session {} - {} UTC, {} QSO(s)",
+            self.start.format("%Y-%m-%d %H:%M"),
+            self.end.format("%Y-%m-%d %H:%M"),
+            self.qsos
+        )
+    }
+}
+
+// Sessions with QSOs in order of start time; only QSOs of the contest (see ConvertOptions)
+// are taken into account
+pub fn split_sessions(
+    adif: &AdifFile,
+    split: &SessionSplit,
+    options: &ConvertOptions,
+    warnings: &mut Vec<String>,
+) -> Vec<LogSession> {
+    let mut times: Vec<NaiveDateTime> = adif
+        .records
+        .iter()
+        .filter(|record| is_contest_qso(record, options, warnings))
+        .filter_map(|record| record.date_time())
+        .collect();
+    times.sort();
+
+    let mut sessions: Vec<LogSession> = Vec::new();
+    match split {
+        SessionSplit::Gap(gap_minutes) => {
+            for time in times {
+                // Session ends a minute after its last QSO
+                match sessions.last_mut() {
+                    Some(session) if time - session.end < Duration::minutes(*gap_minutes as i64) => {
+                        session.end = time + Duration::minutes(1);
+                        session.qsos += 1;
+                    }
+                    _ => sessions.push(LogSession {
+                        start: time,
+                        end: time + Duration::minutes(1),
+                        qsos: 1,
+                    }),
+                }
+            }
+        }
+        SessionSplit::Windows(windows) => {
+            for (start, end) in windows.iter() {
+                let qsos = times.iter().filter(|time| *time >= start && *time < end).count();
+                if qsos > 0 {
+                    sessions.push(LogSession { start: *start, end: *end, qsos });
+                }
+            }
+            sessions.sort_by_key(|session| session.start);
+        }
+    }
+    sessions
+}
@@ -0,0 +1,341 @@
+use crate::locator;
+use crate::reg1test::Reg1testHeader;
+use chrono::{NaiveDate, NaiveTime};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,   // file does not conform to REG1TEST
+    Warning, // file conforms, but content is suspicious
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    pub line: usize, // line number, 0 if violation concerns the whole file
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        };
+        if self.line > 0 {
+            write!(f, "line {}: {}: {}", self.line, severity, self.message)
+        } else {
+            write!(f, "{}: {}", severity, self.message)
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Section {
+    None,
+    Header,
+    Remarks,
+    QSORecords,
+    Unknown,
+}
+
+#[derive(Default)]
+struct Validator {
+    violations: Vec<Violation>,
+    tdate: Option<(NaiveDate, NaiveDate)>,
+}
+
+impl Validator {
+    fn error(&mut self, line: usize, message: String) {
+        self.violations.push(Violation { line, severity: Severity::Error, message });
+    }
+
+    fn warning(&mut self, line: usize, message: String) {
+        self.violations.push(Violation { line, severity: Severity::Warning, message });
+    }
+}
+
+// Checks REG1TEST (EDI) file contents and returns every violation found
+pub fn validate_edi(input: &str) -> Vec<Violation> {
+    let mut v = Validator::default();
+    let mut section = Section::None;
+    let mut header_found = false;
+    let mut header_keys: Vec<String> = Vec::new();
+    let mut qso_records_line = 0;
+    let mut declared_count: Option<usize> = None;
+    let mut qso_count = 0;
+    let mut empty_line = 0; // last empty line in QSO records
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_number = line_index + 1;
+        let trimmed = line.trim();
+        let upper_line = trimmed.to_ascii_uppercase();
+
+        if upper_line.starts_with("[REG1TEST") || upper_line.starts_with("[QSORECORDS")
+            || (section != Section::Remarks && trimmed.starts_with('[') && trimmed.ends_with(']'))
+        {
+            if section == Section::None && !upper_line.starts_with("[REG1TEST") {
+                v.error(line_number, "file must start with [REG1TEST;1]".to_string());
+            }
+            section = if upper_line.starts_with("[REG1TEST") {
+                if upper_line != "[REG1TEST;1]" {
+                    v.error(line_number, format!("section {} should be [REG1TEST;1]", trimmed));
+                }
+                header_found = true;
+                Section::Header
+            } else if upper_line == "[REMARKS]" {
+                Section::Remarks
+            } else if upper_line.starts_with("[QSORECORDS") {
+                qso_records_line = line_number;
+                declared_count = upper_line
+                    .strip_prefix("[QSORECORDS;")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|count| count.parse().ok());
+                if declared_count.is_none() {
+                    v.error(line_number, format!("section {} should be [QSORecords;N]", trimmed));
+                }
+                Section::QSORecords
+            } else {
+                v.warning(line_number, format!("unknown section {}", trimmed));
+                Section::Unknown
+            };
+            continue;
+        }
+
+        match section {
+            Section::None => {
+                if !trimmed.is_empty() {
+                    v.error(line_number, "file must start with [REG1TEST;1]".to_string());
+                    section = Section::Unknown;
+                }
+            }
+            Section::Header => {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                match line.split_once('=') {
+                    Some((key, value)) => {
+                        header_keys.push(key.trim().to_ascii_uppercase());
+                        validate_header_line(&mut v, line_number, key.trim(), value.trim());
+                    }
+                    None => v.error(line_number, "header line without '='".to_string()),
+                }
+            }
+            Section::QSORecords => {
+                if trimmed.is_empty() {
+                    empty_line = line_number;
+                    continue;
+                }
+                if empty_line > 0 {
+                    // Empty lines are allowed only at the end of file
+                    v.warning(empty_line, "empty line in QSO records".to_string());
+                    empty_line = 0;
+                }
+                qso_count += 1;
+                validate_qso_record(&mut v, line_number, trimmed);
+            }
+            Section::Remarks | Section::Unknown => continue,
+        }
+    }
+
+    if header_found {
+        for key in ["TDATE", "PCALL", "PWWLO", "PBAND"] {
+            if !header_keys.iter().any(|k| k == key) {
+                v.error(0, format!("header field {} is missing", key));
+            }
+        }
+        for key in ["TNAME", "PSECT"] {
+            if !header_keys.iter().any(|k| k == key) {
+                v.warning(0, format!("header field {} is missing", key));
+            }
+        }
+    }
+    if qso_records_line == 0 {
+        v.error(0, "[QSORecords;N] section is missing".to_string());
+    } else if let Some(count) = declared_count
+        && count != qso_count
+    {
+        v.error(
+            qso_records_line,
+            format!("[QSORecords;{}] does not match {} QSO records found", count, qso_count),
+        );
+    }
+
+    v.violations.sort_by_key(|violation| violation.line);
+    v.violations
+}
+
+fn validate_header_line(v: &mut Validator, line: usize, key: &str, value: &str) {
+    let mut header = Reg1testHeader::default();
+    if !header.set_field(key, value) {
+        v.warning(line, format!("unknown header field {}", key));
+        return;
+    }
+
+    match key.to_ascii_uppercase().as_str() {
+        "TDATE" => {
+            let dates: Vec<Option<NaiveDate>> = value
+                .split(';')
+                .map(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok().filter(|_| date.len() == 8))
+                .collect();
+            match dates.as_slice() {
+                [Some(start), Some(end)] if start <= end => v.tdate = Some((*start, *end)),
+                [Some(_), Some(_)] => v.error(line, format!("TDate {} ends before it starts", value)),
+                _ => v.error(line, format!("TDate {} should be YYYYMMDD;YYYYMMDD", value)),
+            }
+        }
+        "PCALL" | "RCALL" if !is_valid_call(value) => {
+            v.error(line, format!("{} {} should be 3 to 14 characters", key, value));
+        }
+        "PWWLO" if value.len() != 6 || locator::locator_to_coordinates(value).is_none() => {
+            v.error(line, format!("PWWLo {} is not a valid 6 character locator", value));
+        }
+        "PBAND" => {
+            if Reg1testHeader::get_adif_band(value).is_empty() {
+                v.error(line, format!("PBand {} is not a known band", value));
+            } else if Reg1testHeader::get_band(&Reg1testHeader::get_adif_band(value)) != value {
+                v.warning(line, format!("PBand {} is not written as in REG1TEST", value));
+            }
+        }
+        _ => (),
+    }
+}
+
+fn validate_qso_record(v: &mut Validator, line: usize, row: &str) {
+    let fields: Vec<&str> = row.split(';').collect();
+    if fields.len() != 15 {
+        v.error(line, format!("QSO record has {} fields, expected 15", fields.len()));
+        return;
+    }
+    let (date, time, call, mode, sent_rst, sent_nr, received_rst, received_nr) = (
+        fields[0], fields[1], fields[2], fields[3], fields[4], fields[5], fields[6], fields[7],
+    );
+    let (exchange, wwl, points, new_exchange, new_wwl, new_dxcc, duplicate) = (
+        fields[8], fields[9], fields[10], fields[11], fields[12], fields[13], fields[14],
+    );
+
+    match NaiveDate::parse_from_str(date, "%y%m%d").ok().filter(|_| date.len() == 6) {
+        None => v.error(line, format!("date {} should be YYMMDD", date)),
+        Some(qso_date) => {
+            if let Some((start, end)) = v.tdate
+                && (qso_date < start || qso_date > end)
+            {
+                v.warning(line, format!("date {} is outside TDate", date));
+            }
+        }
+    }
+    if time.len() != 4 || NaiveTime::parse_from_str(time, "%H%M").is_err() {
+        v.error(line, format!("time {} should be HHMM", time));
+    }
+    if !is_valid_call(call) {
+        v.error(line, format!("call {} should be 3 to 14 characters", call));
+    }
+    if mode.len() > 1 || !mode.chars().all(|c| c.is_ascii_digit()) {
+        v.error(line, format!("mode code {} should be 0 to 9", mode));
+    }
+    for (name, rst) in [("sent RST", sent_rst), ("received RST", received_rst)] {
+        if !matches!(rst.len(), 0 | 2 | 3) || !rst.chars().all(|c| c.is_ascii_alphanumeric()) {
+            v.error(line, format!("{} {} should be 2 or 3 characters", name, rst));
+        }
+    }
+    for (name, number) in [("sent QSO number", sent_nr), ("received QSO number", received_nr)] {
+        if !matches!(number.len(), 0 | 3 | 4) || !number.chars().all(|c| c.is_ascii_digit()) {
+            v.error(line, format!("{} {} should be 3 or 4 digits", name, number));
+        }
+    }
+    if exchange.len() > 6 {
+        v.error(line, format!("received exchange {} is longer than 6 characters", exchange));
+    }
+    if !wwl.is_empty() && (!matches!(wwl.len(), 4 | 6) || locator::locator_to_coordinates(wwl).is_none()) {
+        v.error(line, format!("received WWL {} should be a 4 or 6 character locator", wwl));
+    }
+    if points.is_empty() || points.len() > 6 || !points.chars().all(|c| c.is_ascii_digit()) {
+        v.error(line, format!("QSO points {} should be 1 to 6 digits", points));
+    }
+    for (name, flag, expected) in [
+        ("new exchange", new_exchange, "N"),
+        ("new WWL", new_wwl, "N"),
+        ("new DXCC", new_dxcc, "N"),
+        ("duplicate", duplicate, "D"),
+    ] {
+        if !flag.is_empty() && flag != expected {
+            v.error(line, format!("{} flag {} should be empty or {}", name, flag, expected));
+        }
+    }
+    if duplicate == "D" && points.parse::<u32>().is_ok_and(|points| points != 0) {
+        v.warning(line, "duplicate QSO should have 0 points".to_string());
+    }
+}
+
+// 3 to 14 characters: letters, digits and '/'
+fn is_valid_call(call: &str) -> bool {
+    (3..=14).contains(&call.len())
+        && call.chars().all(|c| c.is_ascii_alphanumeric() || c == '/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDI: &str = "[REG1TEST;1]\n\
+        TDate=20260106;20260106\n\
+        PCall=LY2XX\n\
+        PWWLo=KO24PR\n\
+        PBand=144 MHz\n\
+        no equals sign\n\
+        [Remarks]\n\
+        [QSORecords;2]\n\
+        260106;1805;OH1AA;1;59;001;59;005;;KP20LE;608;;N;;\n\
+        260106;1810;SM5BB;0;-10;002;-05;012;;JO89AA;737;;N;;\n";
+
+    fn lines_with(violations: &[Violation], text: &str) -> Vec<usize> {
+        violations
+            .iter()
+            .filter(|v| v.message.contains(text))
+            .map(|v| v.line)
+            .collect()
+    }
+
+    #[test]
+    fn violations_have_line_numbers() {
+        let violations = validate_edi(EDI);
+        assert_eq!(lines_with(&violations, "header line without '='"), vec![6]);
+        assert_eq!(lines_with(&violations, "sent RST -10"), vec![10]);
+        assert_eq!(lines_with(&violations, "received RST -05"), vec![10]);
+        assert!(lines_with(&violations, "OH1AA").is_empty());
+    }
+
+    #[test]
+    fn whole_file_violations_have_no_line_number() {
+        let violations = validate_edi(EDI);
+        assert_eq!(lines_with(&violations, "TNAME is missing"), vec![0]);
+        assert!(violations.iter().filter(|v| v.line == 0).all(|v| !v.to_string().starts_with("line")));
+    }
+
+    #[test]
+    fn converter_output_has_no_errors() {
+        let adi = "test log\n<EOH>\n\
+            <STATION_CALLSIGN:5>LY2XX <MY_GRIDSQUARE:6>KO24PR <CALL:5>OH1AA <QSO_DATE:8>20260106 <TIME_ON:4>1805 <BAND:2>2m <MODE:3>SSB <RST_SENT:2>59 \
+            <STX:3>001 <RST_RCVD:3>599 <SRX:3>005 <GRIDSQUARE:6>KP20LE <EOR>\
+            <CALL:5>SM5BB <QSO_DATE:8>20260106 <TIME_ON:4>1810 <BAND:2>2m <MODE:3>FT8 <RST_SENT:3>-10 \
+            <STX:3>002 <RST_RCVD:3>-05 <EOR>\
+            <CALL:5>OH1AA <QSO_DATE:8>20260106 <TIME_ON:4>1815 <BAND:2>2m <MODE:2>CW <STX:3>003 \
+            <GRIDSQUARE:6>KP20LE <EOR>";
+        let options = crate::ConvertOptions::default();
+        let mut warnings = Vec::new();
+        for log in crate::convert(adi, &options, &mut warnings).unwrap() {
+            let errors: Vec<String> = validate_edi(&log.to_string())
+                .iter()
+                .filter(|v| v.severity == Severity::Error)
+                .map(|v| v.to_string())
+                .collect();
+            assert!(errors.is_empty(), "{:?}", errors);
+        }
+    }
+
+    #[test]
+    fn missing_reg1test_section_is_reported_on_first_text_line() {
+        let violations = validate_edi("\nTDate=20260106;20260106\n");
+        assert_eq!(lines_with(&violations, "file must start with [REG1TEST;1]"), vec![2]);
+    }
+}